@@ -0,0 +1,352 @@
+use crate::config::Config;
+use crate::models::backup::Backup;
+use crate::models::note::Note;
+use crate::models::project::Project;
+use crate::models::tag::{Tag, has_all_tags, parse_tags};
+use crate::models::task::{Duration, Filter, Importance, Priority, Status, Task};
+use crate::models::{calendar, csv_transfer, markdown, todo_txt};
+
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+// Sous-commandes de la ligne de commande : sans sous-commande, le serveur web est lancé.
+// Les autres sous-commandes travaillent directement sur la base, pour les scripts et cron
+
+#[derive(Debug)]
+pub enum Command {
+    Serve,
+    Help,
+    Add(AddParam),
+    List { filter: Filter, tags: Vec<String>, json: bool },
+    Done { id: u32, cascade: bool, json: bool },
+    Export { format: ExportFormat, filter: Filter, tags: Vec<String> },
+}
+
+#[derive(Debug)]
+pub struct AddParam {
+    description: String,
+    priority: Option<Priority>,
+    importance: Option<Importance>,
+    duration: Option<Duration>,
+    project: Option<String>,
+    due_date: Option<NaiveDate>,
+    tags: Vec<String>,
+    json: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ExportFormat {
+    Backup,
+    Csv,
+    TodoTxt,
+    Markdown,
+    Ics,
+}
+
+// options suivies d'une valeur, communes (lues par Config) ou propres aux sous-commandes
+const VALUE_OPTIONS: [&str; 14] = [
+    "-p", "--port", "-d", "--db", "-r", "--retention", "--priority", "--importance", "--duration",
+    "--project", "--due", "--tags", "--filter", "--format",
+];
+const FLAG_OPTIONS: [&str; 4] = ["--json", "--cascade", "-h", "--help"];
+
+impl Command {
+    pub fn from_args(args: &[String]) -> Result<Command, String> {
+        let mut positionals: Vec<&str> = vec![];
+        let mut options: HashMap<&str, &str> = HashMap::new();
+        let mut flags: Vec<&str> = vec![];
+        let mut iter = args.iter().skip(1);
+        while let Some(arg) = iter.next() {
+            if VALUE_OPTIONS.contains(&arg.as_str()) {
+                match iter.next() {
+                    Some(value) => options.insert(arg, value),
+                    None => return Err(format!("Erreur : aucune valeur après {arg}.")),
+                };
+            } else if FLAG_OPTIONS.contains(&arg.as_str()) {
+                flags.push(arg);
+            } else if arg.starts_with("--") {
+                return Err(format!("Erreur : option {arg} inconnue."));
+            } else {
+                positionals.push(arg);
+            }
+        }
+        if flags.contains(&"-h") || flags.contains(&"--help") {
+            return Ok(Command::Help);
+        }
+
+        let json = flags.contains(&"--json");
+        let filter = |default| options.get("--filter").map_or(Ok(default), |value| parse_choice(value, Filter::all(), "filtre"));
+        let tags = parse_tags(options.get("--tags").unwrap_or(&""));
+        let (command, rest) = positionals.split_first().map_or(("serve", &[][..]), |(command, rest)| (*command, rest));
+        let allowed: &[&str] = match command {
+            "add" => &["--priority", "--importance", "--duration", "--project", "--due", "--tags", "--json"],
+            "list" => &["--filter", "--tags", "--json"],
+            "done" => &["--cascade", "--json"],
+            "export" => &["--format", "--filter", "--tags"],
+            _ => &[],
+        };
+        if let Some(option) = options.keys().chain(flags.iter()).find(|option| {
+            !allowed.contains(option) && !["-p", "--port", "-d", "--db", "-r", "--retention"].contains(option)
+        }) {
+            return Err(format!("Erreur : l'option {option} n'est pas permise avec {command}."));
+        }
+
+        let command = match (command, rest) {
+            ("serve", []) => Command::Serve,
+            ("help", []) => Command::Help,
+            ("add", [description]) if !description.trim().is_empty() => Command::Add(AddParam {
+                description: description.trim().to_string(),
+                priority: options.get("--priority").map(|value| parse_choice(value, Priority::all(), "priorité")).transpose()?,
+                importance: options.get("--importance").map(|value| parse_choice(value, Importance::all(), "importance")).transpose()?,
+                duration: options.get("--duration").map(|value| parse_choice(value, Duration::all(), "durée")).transpose()?,
+                project: options.get("--project").map(|name| name.trim().to_string()),
+                due_date: options
+                    .get("--due")
+                    .map(|value| NaiveDate::parse_from_str(value, "%Y-%m-%d"))
+                    .transpose()
+                    .map_err(|_| "Erreur : échéance invalide, format attendu AAAA-MM-JJ.".to_string())?,
+                tags,
+                json,
+            }),
+            ("add", _) => return Err("Erreur : add attend une description.".to_string()),
+            ("list", []) => Command::List { filter: filter(Filter::DailyWork)?, tags, json },
+            ("done", [id]) => Command::Done {
+                id: id.parse().map_err(|_| format!("Erreur : numéro de tâche {id} invalide."))?,
+                cascade: flags.contains(&"--cascade"),
+                json,
+            },
+            ("done", _) => return Err("Erreur : done attend un numéro de tâche.".to_string()),
+            ("export", []) => Command::Export {
+                format: match options.get("--format").copied().unwrap_or("backup") {
+                    "backup" | "json" => ExportFormat::Backup,
+                    "csv" => ExportFormat::Csv,
+                    "todotxt" | "todo.txt" | "txt" => ExportFormat::TodoTxt,
+                    "markdown" | "md" => ExportFormat::Markdown,
+                    "ics" => ExportFormat::Ics,
+                    format => return Err(format!("Erreur : format d'export {format} inconnu.")),
+                },
+                filter: filter(Filter::All)?,
+                tags,
+            },
+            ("serve" | "help" | "list" | "export", _) => return Err(format!("Erreur : trop d'arguments pour {command}.")),
+            (command, _) => return Err(format!("Erreur : commande {command} inconnue.")),
+        };
+        Ok(command)
+    }
+}
+
+// Valeur d'une enum donnée par son nom (UrgentTask, urgent_task, urgent-task) ou son libellé,
+// sans tenir compte de la casse
+
+fn parse_choice<T: Debug + Copy>(value: &str, choices: Vec<(T, String)>, option: &str) -> Result<T, String> {
+    let normalize = |name: &str| name.to_lowercase().replace(['_', '-', ' '], "");
+    let wanted = normalize(value);
+    choices
+        .iter()
+        .find(|(choice, label)| normalize(&format!("{choice:?}")) == wanted || normalize(label) == wanted)
+        .map(|(choice, _)| *choice)
+        .ok_or_else(|| {
+            let names: Vec<String> = choices.iter().map(|(choice, _)| format!("{choice:?}")).collect();
+            format!("Erreur : {option} {value} invalide, valeurs possibles : {}.", names.join(", "))
+        })
+}
+
+// Exécute une sous-commande sur la base de la configuration ; le résultat est écrit sur la
+// sortie standard, les erreurs sont retournées pour être affichées par main
+
+pub fn run(command: Command, config: &Config) -> Result<(), String> {
+    let conn = crate::open_database(config).map_err(sql_error)?;
+    conn.busy_timeout(std::time::Duration::from_secs(5)).map_err(sql_error)?;
+    match command {
+        Command::Serve | Command::Help => Ok(()),
+        Command::Add(param) => add(param, &conn),
+        Command::List { filter, tags, json } => {
+            let task_tags = Tag::by_task(&conn).map_err(sql_error)?;
+            let mut tasks = Task::get_with_filter(&conn, &filter).map_err(sql_error)?;
+            tasks.retain(|task| has_all_tags(task_tags.get(&task.id), &tags));
+            print_tasks(&tasks, json)
+        }
+        Command::Done { id, cascade, json } => done(id, cascade, json, &conn),
+        Command::Export { format, filter, tags } => export(format, filter, &tags, &conn),
+    }
+}
+
+fn sql_error(err: rusqlite::Error) -> String {
+    match err {
+        rusqlite::Error::QueryReturnedNoRows => "Erreur : introuvable.".to_string(),
+        err => format!("Erreur : erreur de la base ({err})."),
+    }
+}
+
+fn add(param: AddParam, conn: &Connection) -> Result<(), String> {
+    let project_id = match &param.project {
+        Some(name) => Some(
+            Project::get_all(true, conn)
+                .map_err(sql_error)?
+                .into_iter()
+                .find(|project| project.name.to_lowercase() == name.to_lowercase())
+                .ok_or_else(|| format!("Erreur : projet {name} inconnu."))?
+                .id,
+        ),
+        None => None,
+    };
+    let default = Task::default();
+    let mut task = Task {
+        description: param.description,
+        priority: param.priority.unwrap_or(default.priority),
+        importance: param.importance.unwrap_or(default.importance),
+        duration: param.duration.unwrap_or(default.duration),
+        project_id,
+        due_date: param.due_date,
+        ..Task::default()
+    };
+    task.insert(conn).map_err(sql_error)?;
+    if !param.tags.is_empty() {
+        Tag::set_for_task(task.id, &param.tags, conn).map_err(sql_error)?;
+    }
+    print_tasks(&[Task::get_by_id(task.id, conn).map_err(sql_error)?], param.json)
+}
+
+// termine une tâche ; ses sous-tâches ouvertes doivent être terminées avant, sauf avec --cascade
+fn done(id: u32, cascade: bool, json: bool, conn: &Connection) -> Result<(), String> {
+    let task = Task::get_by_id(id, conn).map_err(|_| format!("Erreur : tâche {id} introuvable."))?;
+    if task.deleted_at.is_some() {
+        return Err(format!("Erreur : la tâche {id} est dans la corbeille."));
+    }
+    let changed = if cascade {
+        Task::finish_with_children(id, conn).map_err(sql_error)?
+    } else {
+        let open: Vec<String> = Task::open_children(id, conn).map_err(sql_error)?.iter().map(|child| child.id.to_string()).collect();
+        if !open.is_empty() {
+            return Err(format!("Erreur : sous-tâches non terminées : {} (utiliser --cascade).", open.join(", ")));
+        }
+        vec![Task::update_status(id, Status::Finished, conn).map_err(sql_error)?]
+    };
+    print_tasks(&changed, json)
+}
+
+fn export(format: ExportFormat, filter: Filter, tags: &[String], conn: &Connection) -> Result<(), String> {
+    let task_tags = Tag::by_task(conn).map_err(sql_error)?;
+    let mut tasks = Task::get_with_filter(conn, &filter).map_err(sql_error)?;
+    tasks.retain(|task| has_all_tags(task_tags.get(&task.id), tags));
+    let content = match format {
+        // la sauvegarde contient toute la base, sans tenir compte du filtre
+        ExportFormat::Backup => {
+            let backup = Backup::export(conn).map_err(sql_error)?;
+            serde_json::to_string_pretty(&backup).map_err(|err| format!("Erreur : {err}."))? + "\n"
+        }
+        ExportFormat::Csv => csv_transfer::export(&tasks, &task_tags).map_err(|err| format!("Erreur : {err}."))?,
+        ExportFormat::TodoTxt => todo_txt::export(&tasks, &task_tags),
+        ExportFormat::Markdown => markdown::export(&filter.to_string(), tasks, &task_tags),
+        ExportFormat::Ics => {
+            let notes: HashMap<u32, String> =
+                Note::get_all(conn).map_err(sql_error)?.into_iter().map(|note| (note.task_id, note.content)).collect();
+            calendar::export(&format!("Todo - {filter}"), &tasks, &task_tags, &notes)
+        }
+    };
+    print!("{content}");
+    Ok(())
+}
+
+// affiche les tâches en JSON ou en tableau aligné
+fn print_tasks(tasks: &[Task], json: bool) -> Result<(), String> {
+    if json {
+        let content = serde_json::to_string_pretty(tasks).map_err(|err| format!("Erreur : {err}."))?;
+        println!("{content}");
+        return Ok(());
+    }
+    let mut rows = vec![["ID", "Statut", "Priorité", "Échéance", "Projet", "Description"].map(String::from)];
+    for task in tasks {
+        rows.push([
+            task.id.to_string(),
+            task.status.to_string(),
+            task.priority.to_string(),
+            task.due_date.map_or_else(String::new, |date| date.format("%d/%m/%Y").to_string()),
+            task.grouping.clone(),
+            task.description.split_whitespace().collect::<Vec<_>>().join(" "),
+        ]);
+    }
+    let mut widths = [0; 6];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for row in &rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell}{}", " ".repeat(width - cell.chars().count())))
+            .collect();
+        println!("{}", cells.join("  ").trim_end());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Command, String> {
+        let args: Vec<String> = std::iter::once("todo").chain(text.split_whitespace()).map(String::from).collect();
+        Command::from_args(&args)
+    }
+
+    #[test]
+    fn serve_and_help() {
+        assert!(matches!(parse(""), Ok(Command::Serve)));
+        assert!(matches!(parse("-p 8080 --db a.db"), Ok(Command::Serve)));
+        assert!(matches!(parse("help"), Ok(Command::Help)));
+        assert!(parse("list --frob -h").is_err());
+        assert!(matches!(parse("list -h"), Ok(Command::Help)));
+    }
+
+    #[test]
+    fn add() {
+        let args: Vec<String> = ["todo", "add", " Appeler le plombier ", "--priority", "very_urgent", "--importance", "Important",
+            "--duration", "short", "--project", "Maison", "--due", "2026-10-20", "--tags", "a, b", "--json", "--db", "a.db"]
+            .map(String::from)
+            .to_vec();
+        let Ok(Command::Add(param)) = Command::from_args(&args) else { panic!("add attendu") };
+        assert_eq!(param.description, "Appeler le plombier");
+        assert_eq!(param.priority, Some(Priority::VeryUrgent));
+        assert_eq!(param.importance, Some(Importance::Important));
+        assert_eq!(param.duration, Some(Duration::Short));
+        assert_eq!(param.project.as_deref(), Some("Maison"));
+        assert_eq!(param.due_date, NaiveDate::from_ymd_opt(2026, 10, 20));
+        assert_eq!(param.tags, vec!["a".to_string(), "b".to_string()]);
+        assert!(param.json);
+    }
+
+    #[test]
+    fn list_done_export() {
+        let Ok(Command::List { filter, tags, json }) = parse("list --filter due_today --tags x") else { panic!("list attendu") };
+        assert_eq!((filter, tags, json), (Filter::DueToday, vec!["x".to_string()], false));
+        assert!(matches!(parse("list"), Ok(Command::List { filter: Filter::DailyWork, .. })));
+        assert!(matches!(parse("done 12 --cascade"), Ok(Command::Done { id: 12, cascade: true, json: false })));
+        assert!(matches!(parse("export"), Ok(Command::Export { format: ExportFormat::Backup, filter: Filter::All, .. })));
+        assert!(matches!(parse("export --format md --filter blocked"), Ok(Command::Export { format: ExportFormat::Markdown, filter: Filter::Blocked, .. })));
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        for text in [
+            "frob",
+            "add",
+            "add a b",
+            "add x --priority tres",
+            "add x --due 20/10/2026",
+            "list extra",
+            "list --cascade",
+            "done",
+            "done x",
+            "export --format pdf",
+            "list --filter",
+            "--frob",
+        ] {
+            assert!(parse(text).is_err(), "{text}");
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::env;
+use std::path::PathBuf;
+
+// Configuration du serveur, construite à partir de la ligne de commande et de l'environnement
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub port: u16,
+    pub db_path: PathBuf,
+    // nombre de jours avant la purge automatique de la corbeille (0 : jamais)
+    pub retention_days: u32,
+}
+
+const DEFAULT_PORT: u16 = 3000;
+const DB_ENV_VAR: &str = "TODO_DB";
+const DB_FILE_NAME: &str = "todo.db";
+const DEFAULT_RETENTION_DAYS: u32 = 30;
+const RETENTION_ENV_VAR: &str = "TODO_RETENTION_DAYS";
+
+impl Config {
+    // Lit la configuration depuis les arguments du programme et les variables d'environnement
+    pub fn from_env() -> Result<Config, String> {
+        let args: Vec<String> = env::args().collect();
+        Config::from_args(&args)
+    }
+
+    pub fn from_args(args: &[String]) -> Result<Config, String> {
+        let port = match option_value(args, &["--port", "-p"])? {
+            Some(port_str) => parse_port(port_str)?,
+            None => DEFAULT_PORT,
+        };
+        let db_path = match option_value(args, &["--db", "-d"])? {
+            Some(path) => PathBuf::from(path),
+            None => default_db_path()?,
+        };
+        let retention_days = match option_value(args, &["--retention", "-r"])? {
+            Some(days) => parse_retention(days)?,
+            None => match env::var(RETENTION_ENV_VAR) {
+                Ok(days) if !days.is_empty() => parse_retention(&days)?,
+                _ => DEFAULT_RETENTION_DAYS,
+            },
+        };
+        Ok(Config { port, db_path, retention_days })
+    }
+}
+
+// Retourne la valeur qui suit une option (ex : "-p 8080"), ou None si l'option est absente
+
+fn option_value<'a>(args: &'a [String], names: &[&str]) -> Result<Option<&'a str>, String> {
+    match args.iter().position(|arg| names.contains(&arg.as_str())) {
+        Some(pos) => match args.get(pos + 1) {
+            Some(value) => Ok(Some(value)),
+            None => Err(format!("Erreur : aucune valeur après {}.", names.join(" ou "))),
+        },
+        None => Ok(None),
+    }
+}
+
+fn parse_port(port_str: &str) -> Result<u16, String> {
+    match port_str.parse::<u16>() {
+        Ok(port) if port > 79 => Ok(port),
+        Ok(_) => Err("Erreur : le port doit être supérieur à 79.".to_string()),
+        Err(_) => Err("Erreur : port invalide.".to_string()),
+    }
+}
+
+fn parse_retention(days: &str) -> Result<u32, String> {
+    days.parse::<u32>()
+        .map_err(|_| "Erreur : rétention invalide, nombre de jours attendu.".to_string())
+}
+
+// Chemin de la base par défaut : $TODO_DB, sinon le répertoire de données de l'utilisateur
+// ($XDG_DATA_HOME/todo, ~/.local/share/todo ou %APPDATA%\todo sous Windows)
+
+fn default_db_path() -> Result<PathBuf, String> {
+    if let Some(path) = env::var_os(DB_ENV_VAR).filter(|p| !p.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+    Ok(data_dir()?.join("todo").join(DB_FILE_NAME))
+}
+
+fn data_dir() -> Result<PathBuf, String> {
+    let non_empty = |name: &str| env::var_os(name).filter(|p| !p.is_empty()).map(PathBuf::from);
+    if cfg!(windows) && let Some(dir) = non_empty("APPDATA") {
+        return Ok(dir);
+    }
+    if let Some(dir) = non_empty("XDG_DATA_HOME").filter(|p| p.is_absolute()) {
+        return Ok(dir);
+    }
+    match non_empty("HOME") {
+        Some(home) => Ok(home.join(".local").join("share")),
+        None => Err(format!(
+            "Erreur : répertoire de données introuvable, utiliser --db ou {DB_ENV_VAR}."
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn options() {
+        let config = Config::from_args(&args("todo list --db /tmp/t.db -p 8080 -r 7")).unwrap();
+        assert_eq!(config.port, 8080);
+        assert_eq!(config.db_path, PathBuf::from("/tmp/t.db"));
+        assert_eq!(config.retention_days, 7);
+        let config = Config::from_args(&args("todo --port 3001 --db ./a.db --retention 0")).unwrap();
+        assert_eq!((config.port, config.retention_days), (3001, 0));
+        assert_eq!(config.db_path, PathBuf::from("./a.db"));
+    }
+
+    #[test]
+    fn invalid_options() {
+        for text in ["todo -p 79 --db a.db", "todo -p port --db a.db", "todo --db a.db -r -1", "todo --db"] {
+            assert!(Config::from_args(&args(text)).is_err(), "{text}");
+        }
+    }
+}
@@ -0,0 +1,728 @@
+use crate::AppState;
+use crate::controllers::ws::TaskEvent;
+use crate::models::backup::{Backup, RestoreMode, Restored};
+use crate::models::{calendar, csv_transfer, todo_txt};
+use crate::models::history::HistoryEntry;
+use crate::models::project::Project;
+use crate::models::recurrence::Recurrence;
+use crate::models::savepoint::Savepoint;
+use crate::models::scoring::{Factor, MAX_POINTS, ScoreBreakdown, ScoringProfile};
+use crate::models::stats::{Stats, period};
+use crate::models::tag::{Tag, has_all_tags, parse_tags};
+use crate::models::task::{Duration, Filter, Importance, Priority, Status, Task};
+use crate::models::time_entry::{RunningTimer, TimeEntry, TimesheetLine};
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::extract::{Path, Query, State};
+use axum::response::{IntoResponse, Response};
+use axum::{Json, http::StatusCode};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use rusqlite::Connection;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::sync::Arc;
+
+// API JSON sur les tâches (/api/v1/tasks), en parallèle des contrôleurs html
+
+// Erreur renvoyée par l'api sous la forme {"error": "..."} avec le code http adéquat
+
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct ApiErrorBody {
+    error: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(ApiErrorBody { error: self.message })).into_response()
+    }
+}
+
+impl From<rusqlite::Error> for ApiError {
+    fn from(err: rusqlite::Error) -> Self {
+        match err {
+            rusqlite::Error::QueryReturnedNoRows => {
+                ApiError::new(StatusCode::NOT_FOUND, "task not found")
+            }
+            err => {
+                eprintln!("Erreur sql: {:?}", err);
+                ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "database error")
+            }
+        }
+    }
+}
+
+// Les valeurs d'enum invalides (filtre, statut, priorité...) sont des 422
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        let status = match rejection {
+            JsonRejection::JsonDataError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ref r => r.status(),
+        };
+        ApiError::new(status, rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, rejection.body_text())
+    }
+}
+
+type ApiResult<T> = Result<T, ApiError>;
+
+// Distingue un champ absent (None) d'un champ à null (Some(None)) dans un PATCH
+
+fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+// Vérifie que la tâche parente existe et ne crée pas de boucle
+
+fn check_parent(id: Option<u32>, parent_id: Option<u32>, conn: &Connection) -> ApiResult<()> {
+    match parent_id {
+        Some(parent_id) if !Task::can_have_parent(id, parent_id, conn)? => Err(ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "invalid parent_id: unknown task, the task itself or one of its subtasks",
+        )),
+        _ => Ok(()),
+    }
+}
+
+// Vérifie que le projet existe
+
+fn check_project(project_id: Option<u32>, conn: &Connection) -> ApiResult<()> {
+    match project_id.map(|project_id| Project::get_by_id(project_id, conn)) {
+        Some(Err(rusqlite::Error::QueryReturnedNoRows)) => Err(ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "invalid project_id: unknown project",
+        )),
+        Some(Err(err)) => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+// Tâche qui peut être modifiée : 409 si elle est à la corbeille
+
+fn get_active(id: u32, conn: &Connection) -> ApiResult<Task> {
+    let task = Task::get_by_id(id, conn)?;
+    if task.deleted_at.is_some() {
+        return Err(ApiError::new(StatusCode::CONFLICT, "task is in the trash"));
+    }
+    Ok(task)
+}
+
+// Refuse de terminer une tâche dont des sous-tâches sont encore ouvertes
+
+fn check_children_finished(id: u32, conn: &Connection) -> ApiResult<()> {
+    let open: Vec<String> = Task::open_children(id, conn)?.iter().map(|child| child.id.to_string()).collect();
+    if open.is_empty() {
+        return Ok(());
+    }
+    Err(ApiError::new(
+        StatusCode::CONFLICT,
+        format!("subtasks not finished: {}", open.join(", ")),
+    ))
+}
+
+// structure pour récupérer le filtre optionnel de la liste
+
+#[derive(Deserialize)]
+pub struct ListParam {
+    filter: Option<Filter>,
+    // étiquettes que doivent toutes porter les tâches, séparées par des virgules
+    tags: Option<String>,
+}
+
+// Données de création d'une tâche, seule la description est obligatoire
+
+#[derive(Deserialize)]
+pub struct CreateTask {
+    description: String,
+    priority: Option<Priority>,
+    importance: Option<Importance>,
+    duration: Option<Duration>,
+    status: Option<Status>,
+    project_id: Option<u32>,
+    parent_id: Option<u32>,
+    due_date: Option<NaiveDate>,
+    due_time: Option<NaiveTime>,
+    recurrence: Option<Recurrence>,
+}
+
+// Mise à jour partielle : seuls les champs présents sont modifiés
+
+#[derive(Deserialize)]
+pub struct PatchTask {
+    description: Option<String>,
+    priority: Option<Priority>,
+    importance: Option<Importance>,
+    duration: Option<Duration>,
+    status: Option<Status>,
+    #[serde(default, deserialize_with = "double_option")]
+    project_id: Option<Option<u32>>,
+    creation_date: Option<NaiveDate>,
+    // dates appliquées après celles déduites du statut, null les efface
+    #[serde(default, deserialize_with = "double_option")]
+    start_date: Option<Option<NaiveDate>>,
+    #[serde(default, deserialize_with = "double_option")]
+    completion_date: Option<Option<NaiveDate>>,
+    #[serde(default, deserialize_with = "double_option")]
+    parent_id: Option<Option<u32>>,
+    #[serde(default, deserialize_with = "double_option")]
+    due_date: Option<Option<NaiveDate>>,
+    #[serde(default, deserialize_with = "double_option")]
+    due_time: Option<Option<NaiveTime>>,
+    #[serde(default, deserialize_with = "double_option")]
+    recurrence: Option<Option<Recurrence>>,
+}
+
+#[derive(Deserialize)]
+pub struct StatusBody {
+    status: Status,
+    // terminer aussi les sous-tâches encore ouvertes
+    #[serde(default)]
+    cascade: bool,
+}
+
+#[derive(Deserialize)]
+pub struct TagsBody {
+    tags: Vec<String>,
+}
+
+// poids du score modifié : PUT /api/v1/scoring en reçoit la liste
+
+#[derive(Deserialize)]
+pub struct WeightBody {
+    factor: Factor,
+    variant: String,
+    points: i64,
+}
+
+#[derive(Serialize)]
+pub struct RescoredBody {
+    rescored: usize,
+}
+
+#[derive(Deserialize)]
+pub struct BlockerBody {
+    blocker_id: u32,
+}
+
+// période (bornes incluses, ex : 2025-01-31T08:00:00) et tâche optionnelles de l'historique
+
+#[derive(Deserialize)]
+pub struct EventsParam {
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+    task_id: Option<u32>,
+}
+
+// période des statistiques (bornes incluses), les 30 derniers jours par défaut
+
+#[derive(Deserialize)]
+pub struct StatsParam {
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+}
+
+// jour de la feuille de temps, aujourd'hui par défaut
+
+#[derive(Deserialize)]
+pub struct TimesheetParam {
+    day: Option<NaiveDate>,
+}
+
+// import CSV : avec dry_run, seul l'aperçu est renvoyé
+
+#[derive(Deserialize)]
+pub struct ImportParam {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+// mode de restauration d'une sauvegarde, obligatoire
+
+#[derive(Deserialize)]
+pub struct RestoreParam {
+    mode: RestoreMode,
+}
+
+// GET /api/v1/tasks?filter=...
+
+pub async fn list(
+    State(state): State<Arc<AppState>>,
+    param: Result<Query<ListParam>, QueryRejection>,
+) -> ApiResult<Json<Vec<Task>>> {
+    let Query(param) = param?;
+    let conn = state.db.lock().unwrap();
+    let mut tasks = Task::get_with_filter(&conn, &param.filter.unwrap_or(Filter::All))?;
+    if let Some(tags) = param.tags {
+        let (wanted, task_tags) = (parse_tags(&tags), Tag::by_task(&conn)?);
+        tasks.retain(|task| has_all_tags(task_tags.get(&task.id), &wanted));
+    }
+    Ok(Json(tasks))
+}
+
+// GET /api/v1/tasks/{id}
+
+pub async fn get(Path(id): Path<u32>, State(state): State<Arc<AppState>>) -> ApiResult<Json<Task>> {
+    let conn = state.db.lock().unwrap();
+    Ok(Json(Task::get_by_id(id, &conn)?))
+}
+
+// POST /api/v1/tasks
+
+pub async fn create(
+    State(state): State<Arc<AppState>>,
+    input: Result<Json<CreateTask>, JsonRejection>,
+) -> ApiResult<(StatusCode, Json<Task>)> {
+    let Json(input) = input?;
+    let default = Task::default();
+    let mut task = Task {
+        description: input.description,
+        priority: input.priority.unwrap_or(default.priority),
+        importance: input.importance.unwrap_or(default.importance),
+        duration: input.duration.unwrap_or(default.duration),
+        status: input.status.unwrap_or(default.status),
+        project_id: input.project_id,
+        parent_id: input.parent_id,
+        due_date: input.due_date,
+        due_time: input.due_time,
+        recurrence: input.recurrence,
+        ..Task::default()
+    };
+    let conn = state.db.lock().unwrap();
+    check_parent(None, task.parent_id, &conn)?;
+    check_project(task.project_id, &conn)?;
+    task.insert(&conn)?;
+    let task = Task::get_by_id(task.id, &conn)?;
+    state.publish(TaskEvent::Created { task: task.clone() });
+    Ok((StatusCode::CREATED, Json(task)))
+}
+
+// PATCH /api/v1/tasks/{id}
+
+pub async fn patch(
+    Path(id): Path<u32>,
+    State(state): State<Arc<AppState>>,
+    input: Result<Json<PatchTask>, JsonRejection>,
+) -> ApiResult<Json<Task>> {
+    let Json(input) = input?;
+    let conn = state.db.lock().unwrap();
+    let mut task = get_active(id, &conn)?;
+    if let Some(description) = input.description {
+        task.description = description;
+    }
+    if let Some(priority) = input.priority {
+        task.priority = priority;
+    }
+    if let Some(importance) = input.importance {
+        task.importance = importance;
+    }
+    if let Some(duration) = input.duration {
+        task.duration = duration;
+    }
+    if let Some(status) = input.status {
+        task.status = status;
+    }
+    if let Some(project_id) = input.project_id {
+        check_project(project_id, &conn)?;
+        task.project_id = project_id;
+    }
+    if let Some(creation_date) = input.creation_date {
+        task.creation_date = creation_date;
+    }
+    if let Some(due_date) = input.due_date {
+        task.due_date = due_date;
+    }
+    if let Some(due_time) = input.due_time {
+        task.due_time = due_time;
+    }
+    if let Some(recurrence) = input.recurrence {
+        task.recurrence = recurrence;
+    }
+    if let Some(parent_id) = input.parent_id {
+        check_parent(Some(id), parent_id, &conn)?;
+        task.parent_id = parent_id;
+    }
+    if input.status == Some(Status::Finished) {
+        check_children_finished(id, &conn)?;
+    }
+    let tx = Savepoint::new(&conn)?;
+    task.update(id, &tx)?;
+    if input.start_date.is_some() || input.completion_date.is_some() {
+        Task::change_dates(id, input.start_date, input.completion_date, &tx)?;
+    }
+    tx.commit()?;
+    state.publish_changed(id, &conn)?;
+    Ok(Json(Task::get_by_id(id, &conn)?))
+}
+
+// PUT /api/v1/tasks/{id}/status
+
+pub async fn update_status(
+    Path(id): Path<u32>,
+    State(state): State<Arc<AppState>>,
+    input: Result<Json<StatusBody>, JsonRejection>,
+) -> ApiResult<Json<Task>> {
+    let Json(input) = input?;
+    let conn = state.db.lock().unwrap();
+    get_active(id, &conn)?;
+    let changed = match (input.status, input.cascade) {
+        (Status::Finished, true) => Task::finish_with_children(id, &conn)?,
+        (Status::Finished, false) => {
+            check_children_finished(id, &conn)?;
+            vec![Task::update_status(id, input.status, &conn)?]
+        }
+        _ => vec![Task::update_status(id, input.status, &conn)?],
+    };
+    for task in changed {
+        state.publish_changed(task.id, &conn)?;
+    }
+    Ok(Json(Task::get_by_id(id, &conn)?))
+}
+
+// POST /api/v1/tasks/{id}/restore
+
+pub async fn restore(Path(id): Path<u32>, State(state): State<Arc<AppState>>) -> ApiResult<Json<Task>> {
+    let conn = state.db.lock().unwrap();
+    match Task::restore(id, &conn)? {
+        0 => Err(ApiError::new(StatusCode::NOT_FOUND, "task not found in trash")),
+        _ => {
+            let task = Task::get_by_id(id, &conn)?;
+            state.publish(TaskEvent::Created { task: task.clone() });
+            // les tâches qui l'attendent ont pu être rebloquées
+            state.publish_dependents(id, &conn)?;
+            Ok(Json(task))
+        }
+    }
+}
+
+// GET /api/v1/tasks/{id}/blockers
+
+pub async fn blockers(Path(id): Path<u32>, State(state): State<Arc<AppState>>) -> ApiResult<Json<Vec<Task>>> {
+    let conn = state.db.lock().unwrap();
+    Task::get_by_id(id, &conn)?;
+    Ok(Json(Task::get_blockers(id, &conn)?))
+}
+
+// POST /api/v1/tasks/{id}/blockers, 422 si la dépendance créerait un cycle
+
+pub async fn add_blocker(
+    Path(id): Path<u32>,
+    State(state): State<Arc<AppState>>,
+    input: Result<Json<BlockerBody>, JsonRejection>,
+) -> ApiResult<Json<Task>> {
+    let Json(input) = input?;
+    let conn = state.db.lock().unwrap();
+    get_active(id, &conn)?;
+    if !Task::can_depend_on(id, input.blocker_id, &conn)? {
+        return Err(ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "invalid blocker_id: unknown task, the task itself or a dependency cycle",
+        ));
+    }
+    Task::add_blocker(id, input.blocker_id, &conn)?;
+    state.publish_changed(id, &conn)?;
+    Ok(Json(Task::get_by_id(id, &conn)?))
+}
+
+// DELETE /api/v1/tasks/{id}/blockers/{blocker_id}
+
+pub async fn remove_blocker(
+    Path((id, blocker_id)): Path<(u32, u32)>,
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<StatusCode> {
+    let conn = state.db.lock().unwrap();
+    get_active(id, &conn)?;
+    match Task::remove_blocker(id, blocker_id, &conn)? {
+        0 => Err(ApiError::new(StatusCode::NOT_FOUND, "dependency not found")),
+        _ => {
+            state.publish_changed(id, &conn)?;
+            Ok(StatusCode::NO_CONTENT)
+        }
+    }
+}
+
+// GET /api/v1/tasks/{id}/tags
+
+pub async fn tags(Path(id): Path<u32>, State(state): State<Arc<AppState>>) -> ApiResult<Json<Vec<String>>> {
+    let conn = state.db.lock().unwrap();
+    Task::get_by_id(id, &conn)?;
+    Ok(Json(Tag::for_task(id, &conn)?))
+}
+
+// PUT /api/v1/tasks/{id}/tags (remplace toutes les étiquettes de la tâche)
+
+pub async fn set_tags(
+    Path(id): Path<u32>,
+    State(state): State<Arc<AppState>>,
+    input: Result<Json<TagsBody>, JsonRejection>,
+) -> ApiResult<Json<Vec<String>>> {
+    let Json(input) = input?;
+    let conn = state.db.lock().unwrap();
+    get_active(id, &conn)?;
+    Tag::set_for_task(id, &parse_tags(&input.tags.join(",")), &conn)?;
+    state.publish_changed(id, &conn)?;
+    Ok(Json(Tag::for_task(id, &conn)?))
+}
+
+// GET /api/v1/tasks/{id}/score (détail du calcul avec le profil en vigueur)
+
+pub async fn score(Path(id): Path<u32>, State(state): State<Arc<AppState>>) -> ApiResult<Json<ScoreBreakdown>> {
+    let conn = state.db.lock().unwrap();
+    let task = Task::get_by_id(id, &conn)?;
+    let waiting = Task::waiting_count(id, &conn)?;
+    Ok(Json(ScoringProfile::load(&conn)?.breakdown(&task, waiting)))
+}
+
+// DELETE /api/v1/tasks/{id} (mise à la corbeille)
+
+pub async fn delete(Path(id): Path<u32>, State(state): State<Arc<AppState>>) -> ApiResult<StatusCode> {
+    let conn = state.db.lock().unwrap();
+    match Task::delete(id, &conn)? {
+        0 => Err(ApiError::new(StatusCode::NOT_FOUND, "task not found")),
+        _ => {
+            state.publish(TaskEvent::Removed { id });
+            state.publish_dependents(id, &conn)?;
+            state.publish_timer(&conn)?;
+            Ok(StatusCode::NO_CONTENT)
+        }
+    }
+}
+
+// GET /api/v1/tasks/{id}/events
+
+pub async fn task_events(Path(id): Path<u32>, State(state): State<Arc<AppState>>) -> ApiResult<Json<Vec<HistoryEntry>>> {
+    let conn = state.db.lock().unwrap();
+    Ok(Json(HistoryEntry::for_task(id, &conn)?))
+}
+
+// GET /api/v1/events?from=...&to=...&task_id=...
+
+pub async fn events(
+    State(state): State<Arc<AppState>>,
+    param: Result<Query<EventsParam>, QueryRejection>,
+) -> ApiResult<Json<Vec<HistoryEntry>>> {
+    let Query(param) = param?;
+    let conn = state.db.lock().unwrap();
+    Ok(Json(HistoryEntry::between(&conn, param.from, param.to, param.task_id)?))
+}
+
+// GET /api/v1/projects (archivés compris)
+
+pub async fn projects(State(state): State<Arc<AppState>>) -> ApiResult<Json<Vec<Project>>> {
+    let conn = state.db.lock().unwrap();
+    Ok(Json(Project::get_all(true, &conn)?))
+}
+
+// GET /api/v1/tags
+
+pub async fn all_tags(State(state): State<Arc<AppState>>) -> ApiResult<Json<Vec<Tag>>> {
+    let conn = state.db.lock().unwrap();
+    Ok(Json(Tag::get_all(&conn)?))
+}
+
+// GET /api/v1/scoring
+
+pub async fn scoring(State(state): State<Arc<AppState>>) -> ApiResult<Json<ScoringProfile>> {
+    let conn = state.db.lock().unwrap();
+    Ok(Json(ScoringProfile::load(&conn)?))
+}
+
+// PUT /api/v1/scoring (les poids absents gardent leur valeur, puis tout est recalculé)
+
+pub async fn set_scoring(
+    State(state): State<Arc<AppState>>,
+    input: Result<Json<Vec<WeightBody>>, JsonRejection>,
+) -> ApiResult<Json<ScoringProfile>> {
+    let Json(input) = input?;
+    let conn = state.db.lock().unwrap();
+    let mut profile = ScoringProfile::load(&conn)?;
+    for body in input {
+        let Some(weight) = profile.weights.iter_mut().find(|w| w.factor == body.factor && w.variant == body.variant) else {
+            return Err(ApiError::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("unknown weight {:?}.{}", body.factor, body.variant),
+            ));
+        };
+        if !ScoringProfile::valid_points(body.points) {
+            return Err(ApiError::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("points of {:?}.{} must be between -{MAX_POINTS} and {MAX_POINTS}", body.factor, body.variant),
+            ));
+        }
+        weight.points = body.points;
+    }
+    profile.save(&conn)?;
+    state.rescore_all(&conn)?;
+    Ok(Json(profile))
+}
+
+// POST /api/v1/scoring/recompute
+
+pub async fn recompute_scores(State(state): State<Arc<AppState>>) -> ApiResult<Json<RescoredBody>> {
+    let conn = state.db.lock().unwrap();
+    Ok(Json(RescoredBody { rescored: state.rescore_all(&conn)? }))
+}
+
+// GET /api/v1/stats?from=...&to=...
+
+pub async fn stats(
+    State(state): State<Arc<AppState>>,
+    param: Result<Query<StatsParam>, QueryRejection>,
+) -> ApiResult<Json<Stats>> {
+    let Query(param) = param?;
+    let (from, to) = period(param.from, param.to)
+        .ok_or_else(|| ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid period: from must not be after to, at most one year"))?;
+    let conn = state.db.lock().unwrap();
+    Ok(Json(Stats::compute(from, to, &conn)?))
+}
+
+// GET /api/v1/timer (null si aucun chronomètre ne tourne)
+
+pub async fn timer(State(state): State<Arc<AppState>>) -> ApiResult<Json<Option<RunningTimer>>> {
+    let conn = state.db.lock().unwrap();
+    Ok(Json(TimeEntry::running(&conn)?))
+}
+
+// POST /api/v1/tasks/{id}/timer/start (arrête le chronomètre d'une autre tâche)
+
+pub async fn start_timer(Path(id): Path<u32>, State(state): State<Arc<AppState>>) -> ApiResult<Json<Option<RunningTimer>>> {
+    let conn = state.db.lock().unwrap();
+    let task = Task::get_by_id(id, &conn)?;
+    if task.deleted_at.is_some() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "task not found"));
+    }
+    if task.status.is_resolved() {
+        return Err(ApiError::new(StatusCode::CONFLICT, "task is finished or canceled"));
+    }
+    for changed in TimeEntry::start(id, &conn)? {
+        state.publish_changed(changed, &conn)?;
+    }
+    state.publish_timer(&conn)?;
+    Ok(Json(TimeEntry::running(&conn)?))
+}
+
+// POST /api/v1/timer/stop
+
+pub async fn stop_timer(State(state): State<Arc<AppState>>) -> ApiResult<StatusCode> {
+    let conn = state.db.lock().unwrap();
+    if let Some(id) = TimeEntry::stop(&conn)? {
+        state.publish_changed(id, &conn)?;
+        state.publish_timer(&conn)?;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// GET /api/v1/tasks/{id}/time_entries
+
+pub async fn time_entries(Path(id): Path<u32>, State(state): State<Arc<AppState>>) -> ApiResult<Json<Vec<TimeEntry>>> {
+    let conn = state.db.lock().unwrap();
+    Task::get_by_id(id, &conn)?;
+    Ok(Json(TimeEntry::for_task(id, &conn)?))
+}
+
+// GET /api/v1/timesheet?day=...
+
+pub async fn timesheet(
+    State(state): State<Arc<AppState>>,
+    param: Result<Query<TimesheetParam>, QueryRejection>,
+) -> ApiResult<Json<Vec<TimesheetLine>>> {
+    let Query(param) = param?;
+    let conn = state.db.lock().unwrap();
+    let day = param.day.unwrap_or_else(|| chrono::Local::now().date_naive());
+    Ok(Json(TimeEntry::timesheet(day, &conn)?))
+}
+
+// POST /api/v1/tasks/import?dry_run=true (corps : le fichier CSV, colonnes associées
+// d'après les en-têtes). Un fichier avec des erreurs est refusé (422) avec son aperçu
+
+pub async fn import_csv(
+    State(state): State<Arc<AppState>>,
+    param: Result<Query<ImportParam>, QueryRejection>,
+    body: String,
+) -> ApiResult<Response> {
+    let Query(param) = param?;
+    let conn = state.db.lock().unwrap();
+    let preview = csv_transfer::parse(&body, None, &conn)?;
+    if preview.error_count() > 0 {
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, Json(preview)).into_response());
+    }
+    if param.dry_run {
+        return Ok(Json(preview).into_response());
+    }
+    let mut tasks = vec![];
+    for id in csv_transfer::import(&preview.rows, &conn)? {
+        let task = Task::get_by_id(id, &conn)?;
+        state.publish(TaskEvent::Created { task: task.clone() });
+        tasks.push(task);
+    }
+    Ok((StatusCode::CREATED, Json(tasks)).into_response())
+}
+
+// GET /api/v1/backup
+
+pub async fn backup(State(state): State<Arc<AppState>>) -> ApiResult<Json<Backup>> {
+    let conn = state.db.lock().unwrap();
+    Ok(Json(Backup::export(&conn)?))
+}
+
+// POST /api/v1/backup/restore?mode=replace|merge (corps : le document de sauvegarde)
+
+pub async fn restore_backup(
+    State(state): State<Arc<AppState>>,
+    param: Result<Query<RestoreParam>, QueryRejection>,
+    input: Result<Json<Backup>, JsonRejection>,
+) -> ApiResult<Json<Restored>> {
+    let Query(param) = param?;
+    let Json(backup) = input?;
+    let errors = backup.validate();
+    if !errors.is_empty() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, errors.join("; ")));
+    }
+    let conn = state.db.lock().unwrap();
+    let restored = backup.restore(param.mode, &conn)?;
+    state.publish_restored(&restored, &conn)?;
+    Ok(Json(restored))
+}
+
+// POST /api/v1/calendar/import (corps : fichier iCalendar), tout ou rien
+
+pub async fn import_calendar(State(state): State<Arc<AppState>>, body: String) -> ApiResult<(StatusCode, Json<Vec<Task>>)> {
+    let conn = state.db.lock().unwrap();
+    let tasks = calendar::import(&body, &conn)?.map_err(|errors| ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, errors.join("; ")))?;
+    for task in &tasks {
+        state.publish(TaskEvent::Created { task: task.clone() });
+    }
+    Ok((StatusCode::CREATED, Json(tasks)))
+}
+
+// POST /api/v1/todo_txt/import (corps : fichier todo.txt), tout ou rien
+
+pub async fn import_todo_txt(State(state): State<Arc<AppState>>, body: String) -> ApiResult<(StatusCode, Json<Vec<Task>>)> {
+    let conn = state.db.lock().unwrap();
+    let tasks = todo_txt::import(&body, &conn)?.map_err(|errors| ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, errors.join("; ")))?;
+    for task in &tasks {
+        state.publish(TaskEvent::Created { task: task.clone() });
+    }
+    Ok((StatusCode::CREATED, Json(tasks)))
+}
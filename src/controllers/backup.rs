@@ -0,0 +1,99 @@
+use crate::AppState;
+use crate::error::{AppError, AppResult};
+use crate::models::backup::{Backup, RestoreMode};
+use axum::extract::rejection::{FormRejection, QueryRejection};
+use axum::extract::{Form, Query, State};
+use axum::http::header;
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use chrono::Local;
+use minijinja::context;
+use serde::Deserialize;
+use std::sync::Arc;
+
+// Paramètres ajoutés après une restauration : nombre de tâches restaurées et mode
+
+#[derive(Deserialize, Debug)]
+pub struct RestoredParam {
+    restored: Option<usize>,
+    mode: Option<RestoreMode>,
+}
+
+// structure pour récupérer le formulaire de restauration
+
+#[derive(Deserialize, Debug)]
+pub struct RestoreForm {
+    content: String,
+    mode: RestoreMode,
+}
+
+// affiche la page de sauvegarde, avec un éventuel message ou les erreurs du document
+
+fn render_index(state: &AppState, message: Option<String>, errors: &[String]) -> AppResult<Html<String>> {
+    let template = state.env.get_template("backup.index")?;
+    let rendered = template.render(context! {
+        title => "Sauvegarde",
+        message => message,
+        errors => errors,
+    })?;
+    Ok(Html(rendered))
+}
+
+// page de sauvegarde et de restauration
+
+pub async fn index(
+    State(state): State<Arc<AppState>>,
+    param: Result<Query<RestoredParam>, QueryRejection>,
+) -> AppResult<Html<String>> {
+    let Query(param) = param?;
+    let message = param.restored.map(|nb| match param.mode {
+        Some(RestoreMode::Merge) => format!("Sauvegarde fusionnée, {nb} tâche(s) ajoutée(s)"),
+        _ => format!("Sauvegarde restaurée, {nb} tâche(s)"),
+    });
+    render_index(&state, message, &[])
+}
+
+// télécharge la sauvegarde complète de la base
+
+pub async fn export(State(state): State<Arc<AppState>>) -> AppResult<Response> {
+    let backup = {
+        let conn = state.db.lock().unwrap();
+        Backup::export(&conn)?
+    };
+    let content = serde_json::to_string_pretty(&backup)
+        .map_err(|err| AppError::BadRequest(format!("Sauvegarde impossible : {err}")))?;
+    let filename = format!("todo-{}.json", Local::now().format("%Y-%m-%d"));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}\"")),
+        ],
+        content,
+    )
+        .into_response())
+}
+
+// restaure une sauvegarde : le document est vérifié avant toute modification de la base
+
+pub async fn restore(
+    State(state): State<Arc<AppState>>,
+    input: Result<Form<RestoreForm>, FormRejection>,
+) -> AppResult<Redirect> {
+    let Form(input) = input?;
+    let backup: Backup = match serde_json::from_str(&input.content) {
+        Ok(backup) => backup,
+        Err(err) => {
+            let errors = [format!("Document illisible : {err}")];
+            return Err(AppError::InvalidForm(render_index(&state, None, &errors)?));
+        }
+    };
+    let errors = backup.validate();
+    if !errors.is_empty() {
+        return Err(AppError::InvalidForm(render_index(&state, None, &errors)?));
+    }
+
+    let conn = state.db.lock().unwrap();
+    let restored = backup.restore(input.mode, &conn)?;
+    state.publish_restored(&restored, &conn)?;
+    let mode = if input.mode == RestoreMode::Merge { "merge" } else { "replace" };
+    Ok(Redirect::to(&format!("/backup?restored={}&mode={mode}", restored.tasks.len())))
+}
@@ -0,0 +1,95 @@
+use crate::AppState;
+use crate::controllers::ws::TaskEvent;
+use crate::error::{AppError, AppResult};
+use crate::models::calendar;
+use crate::models::note::Note;
+use crate::models::project::Project;
+use crate::models::tag::Tag;
+use crate::models::task::{Filter, Task};
+use axum::extract::rejection::{FormRejection, QueryRejection};
+use axum::extract::{Form, Query, State};
+use axum::http::header;
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use minijinja::context;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// structure pour récupérer le filtre et le projet du calendrier
+
+#[derive(Deserialize)]
+pub struct FeedParam {
+    filter: Option<Filter>,
+    project: Option<u32>,
+}
+
+// structure pour récupérer le fichier iCalendar collé ou chargé dans le formulaire
+
+#[derive(Deserialize)]
+pub struct ImportForm {
+    content: String,
+}
+
+// calendrier iCalendar des tâches d'un filtre (toutes par défaut), éventuellement d'un
+// seul projet, auquel les clients de calendrier peuvent s'abonner
+
+pub async fn feed(
+    param: Result<Query<FeedParam>, QueryRejection>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Response> {
+    let Query(param) = param?;
+    let filter = param.filter.unwrap_or(Filter::All);
+
+    let conn = state.db.lock().unwrap();
+
+    let mut tasks = Task::get_with_filter(&conn, &filter)?;
+    let mut name = format!("Todo - {filter}");
+    if let Some(project_id) = param.project {
+        let project = Project::get_by_id(project_id, &conn)?;
+        tasks.retain(|task| task.project_id == Some(project_id));
+        name = format!("{name} - {}", project.name);
+    }
+    let notes: HashMap<u32, String> = Note::get_all(&conn)?.into_iter().map(|note| (note.task_id, note.content)).collect();
+    let ics = calendar::export(&name, &tasks, &Tag::by_task(&conn)?, &notes);
+    Ok(([(header::CONTENT_TYPE, "text/calendar; charset=utf-8")], ics).into_response())
+}
+
+// affiche le formulaire d'import, avec les erreurs du fichier refusé
+
+fn render_import(state: &AppState, content: &str, errors: &[String]) -> AppResult<Html<String>> {
+    let template = state.env.get_template("calendar.import")?;
+    let rendered = template.render(context! {
+        title => "Import iCalendar",
+        content => content,
+        errors => errors,
+    })?;
+    Ok(Html(rendered))
+}
+
+// retourne le formulaire d'import
+
+pub async fn import_form(State(state): State<Arc<AppState>>) -> AppResult<Html<String>> {
+    render_import(&state, "", &[])
+}
+
+// importe les tâches du fichier iCalendar
+
+pub async fn import(
+    State(state): State<Arc<AppState>>,
+    input: Result<Form<ImportForm>, FormRejection>,
+) -> AppResult<Redirect> {
+    let Form(input) = input?;
+    let imported = {
+        let conn = state.db.lock().unwrap();
+        calendar::import(&input.content, &conn)?
+    };
+    match imported {
+        Ok(tasks) => {
+            for task in tasks {
+                state.publish(TaskEvent::Created { task });
+            }
+            Ok(Redirect::to("/task"))
+        }
+        Err(errors) => Err(AppError::InvalidForm(render_import(&state, &input.content, &errors)?)),
+    }
+}
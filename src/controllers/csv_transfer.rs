@@ -0,0 +1,132 @@
+use crate::AppState;
+use crate::controllers::ws::TaskEvent;
+use crate::error::{AppError, AppResult};
+use crate::models::csv_transfer::{self, CsvField, CsvImport};
+use crate::models::tag::{Tag, has_all_tags, parse_tags};
+use crate::models::task::{Duration, Filter, Importance, Priority, Status, Task};
+use axum::extract::rejection::{FormRejection, QueryRejection};
+use axum::extract::{Form, Query, State};
+use axum::http::header;
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use minijinja::context;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// structure pour récupérer le filtre des tâches à exporter
+
+#[derive(Deserialize)]
+pub struct ExportParam {
+    filter: Option<Filter>,
+    // étiquettes que doivent toutes porter les tâches, séparées par des virgules
+    #[serde(default)]
+    tags: String,
+}
+
+// exporte en CSV les tâches d'un filtre (toutes par défaut)
+
+pub async fn export(
+    param: Result<Query<ExportParam>, QueryRejection>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Response> {
+    let Query(param) = param?;
+    let filter = param.filter.unwrap_or(Filter::All);
+    let tags = parse_tags(&param.tags);
+
+    let conn = state.db.lock().unwrap();
+
+    let task_tags = Tag::by_task(&conn)?;
+    let mut tasks = Task::get_with_filter(&conn, &filter)?;
+    tasks.retain(|task| has_all_tags(task_tags.get(&task.id), &tags));
+    let content = csv_transfer::export(&tasks, &task_tags)
+        .map_err(|err| AppError::BadRequest(format!("Export impossible : {err}")))?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"taches-{filter:?}.csv\"")),
+        ],
+        content,
+    )
+        .into_response())
+}
+
+// Correspondance choisie sur la page d'aperçu (champs map.0, map.1...), absente lors
+// du premier envoi du fichier : les colonnes sont alors associées d'après leur en-tête
+
+fn mapping(input: &HashMap<String, String>) -> Option<Vec<Option<CsvField>>> {
+    let mut columns: Vec<(usize, Option<CsvField>)> = input
+        .iter()
+        .filter_map(|(key, value)| Some((key.strip_prefix("map.")?.parse().ok()?, CsvField::from_name(value))))
+        .collect();
+    if columns.is_empty() {
+        return None;
+    }
+    columns.sort_by_key(|(i, _)| *i);
+    let mut mapping = vec![None; columns.last().map_or(0, |(i, _)| i + 1)];
+    for (i, field) in columns {
+        mapping[i] = field;
+    }
+    Some(mapping)
+}
+
+// affiche le formulaire d'import, avec l'aperçu du fichier s'il a été envoyé
+
+fn render_import(state: &AppState, content: &str, preview: Option<&CsvImport>, error: Option<&str>) -> AppResult<Html<String>> {
+    let template = state.env.get_template("csv.import")?;
+    let rendered = template.render(context! {
+        title => "Import CSV",
+        content => content,
+        preview => preview,
+        error_count => preview.map(CsvImport::error_count),
+        error => error,
+        all_field => CsvField::all(),
+        all_priority => Priority::all(),
+        all_importance => Importance::all(),
+        all_duration => Duration::all(),
+        all_status => Status::all(),
+    })?;
+    Ok(Html(rendered))
+}
+
+// retourne le formulaire d'import
+
+pub async fn import_form(State(state): State<Arc<AppState>>) -> AppResult<Html<String>> {
+    render_import(&state, "", None, None)
+}
+
+// aperçu de l'import : colonnes associées et erreurs de chaque ligne, rien n'est enregistré
+
+pub async fn preview(
+    State(state): State<Arc<AppState>>,
+    input: Result<Form<HashMap<String, String>>, FormRejection>,
+) -> AppResult<Html<String>> {
+    let Form(input) = input?;
+    let content = input.get("csv").cloned().unwrap_or_default();
+    let preview = {
+        let conn = state.db.lock().unwrap();
+        csv_transfer::parse(&content, mapping(&input), &conn)?
+    };
+    render_import(&state, &content, Some(&preview), None)
+}
+
+// importe le fichier après une nouvelle vérification : une seule erreur et rien n'est importé
+
+pub async fn import(
+    State(state): State<Arc<AppState>>,
+    input: Result<Form<HashMap<String, String>>, FormRejection>,
+) -> AppResult<Redirect> {
+    let Form(input) = input?;
+    let content = input.get("csv").cloned().unwrap_or_default();
+    let conn = state.db.lock().unwrap();
+
+    let preview = csv_transfer::parse(&content, mapping(&input), &conn)?;
+    if preview.error_count() > 0 {
+        drop(conn);
+        let error = "Le fichier contient des erreurs, aucune tâche n'a été importée";
+        return Err(AppError::InvalidForm(render_import(&state, &content, Some(&preview), Some(error))?));
+    }
+    for id in csv_transfer::import(&preview.rows, &conn)? {
+        state.publish(TaskEvent::Created { task: Task::get_by_id(id, &conn)? });
+    }
+    Ok(Redirect::to("/task"))
+}
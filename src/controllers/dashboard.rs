@@ -0,0 +1,187 @@
+use crate::AppState;
+use crate::error::{AppError, AppResult};
+use crate::models::matrix::Quadrant;
+use crate::models::project::Project;
+use crate::models::task::{Filter, Status, Task};
+use axum::extract::rejection::{FormRejection, QueryRejection};
+use axum::extract::{Form, Path, Query, State};
+use axum::response::{Html, Redirect};
+use minijinja::context;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+// Structure pour récupérer le quadrant de destination d'une tâche déplacée
+
+#[derive(Deserialize, Debug)]
+pub struct QuadrantForm {
+    quadrant: Quadrant,
+}
+
+// Paramètre du tableau kanban : lanes=project découpe le tableau en lignes par projet
+
+#[derive(Deserialize, Debug)]
+pub struct KanbanParam {
+    #[serde(default)]
+    lanes: String,
+}
+
+// Colonnes du tableau kanban, dans l'ordre de progression d'une tâche
+
+const KANBAN_COLUMNS: [Status; 5] = [Status::ToDo, Status::InProgress, Status::Blocked, Status::Finished, Status::Canceled];
+
+// Une ligne du tableau kanban : le projet (aucun pour les tâches sans projet ou sans
+// découpage) et les tâches de chaque colonne
+
+#[derive(Serialize, Debug)]
+struct Lane<'a> {
+    project: Option<&'a Project>,
+    columns: Vec<(Status, Vec<&'a Task>)>,
+}
+
+fn lane<'a>(project: Option<&'a Project>, tasks: &[&'a Task]) -> Lane<'a> {
+    let columns = KANBAN_COLUMNS
+        .into_iter()
+        .map(|status| (status, tasks.iter().copied().filter(|task| task.status == status).collect()))
+        .collect();
+    Lane { project, columns }
+}
+
+// matrice d'Eisenhower des tâches en cours, les tâches non classées sont affichées à part
+
+pub async fn matrix(State(state): State<Arc<AppState>>) -> AppResult<Html<String>> {
+    let template = state.env.get_template("dashboard.matrix")?;
+
+    let conn = state.db.lock().unwrap();
+
+    let tasks = Task::get_with_filter(&conn, &Filter::DailyWork)?;
+    let quadrants: Vec<(Quadrant, String, Vec<&Task>)> = Quadrant::all()
+        .into_iter()
+        .map(|(quadrant, label)| {
+            let tasks = tasks.iter().filter(|task| Quadrant::of(task) == Some(quadrant)).collect();
+            (quadrant, label, tasks)
+        })
+        .collect();
+    let unclassified: Vec<&Task> = tasks.iter().filter(|task| Quadrant::of(task).is_none()).collect();
+
+    let rendered = template.render(context! {
+        title => "Matrice d'Eisenhower",
+        quadrants => quadrants,
+        unclassified => unclassified,
+        projects => Project::by_id(&conn)?,
+        all_status => Status::all(),
+    })?;
+    Ok(Html(rendered))
+}
+
+// Tâche affichée dans la matrice : ni à la corbeille, ni terminée ou annulée
+
+fn get_matrix_task(id: u32, conn: &Connection) -> AppResult<Task> {
+    let task = Task::get_by_id(id, conn)?;
+    if task.deleted_at.is_some() || matches!(task.status, Status::Finished | Status::Canceled) {
+        return Err(AppError::NotFound);
+    }
+    Ok(task)
+}
+
+// carte d'une tâche de la matrice, rechargée par la page à chaque modification
+
+pub async fn card(Path(id): Path<u32>, State(state): State<Arc<AppState>>) -> AppResult<Html<String>> {
+    let template = state.env.get_template("dashboard.card")?;
+
+    let conn = state.db.lock().unwrap();
+
+    let task = get_matrix_task(id, &conn)?;
+    let rendered = template.render(context! {
+        quadrant => Quadrant::of(&task),
+        task => task,
+        projects => Project::by_id(&conn)?,
+        all_status => Status::all(),
+    })?;
+    Ok(Html(rendered))
+}
+
+// déplace une tâche dans un quadrant en modifiant sa priorité et son importance
+
+pub async fn move_task(
+    Path(id): Path<u32>,
+    State(state): State<Arc<AppState>>,
+    input: Result<Form<QuadrantForm>, FormRejection>,
+) -> AppResult<Redirect> {
+    let Form(input) = input?;
+    let conn = state.db.lock().unwrap();
+
+    let mut task = get_matrix_task(id, &conn)?;
+    input.quadrant.apply(&mut task);
+    task.update(id, &conn)?;
+    state.publish_changed(id, &conn)?;
+
+    Ok(Redirect::to("/dashboard/matrix"))
+}
+
+// tableau kanban : une colonne par statut, éventuellement une ligne par projet
+
+pub async fn kanban(
+    State(state): State<Arc<AppState>>,
+    param: Result<Query<KanbanParam>, QueryRejection>,
+) -> AppResult<Html<String>> {
+    let Query(param) = param?;
+    let template = state.env.get_template("dashboard.kanban")?;
+
+    let conn = state.db.lock().unwrap();
+
+    let tasks = Task::get_with_filter(&conn, &Filter::All)?;
+    let all_projects = Project::get_all(true, &conn)?;
+    let by_project = param.lanes == "project";
+    let lanes: Vec<Lane> = if by_project {
+        // les projets sans tâche n'ont pas de ligne, les tâches sans projet sont à la fin
+        all_projects
+            .iter()
+            .map(Some)
+            .chain([None])
+            .map(|project| {
+                let tasks: Vec<&Task> = tasks.iter().filter(|task| task.project_id == project.map(|p| p.id)).collect();
+                lane(project, &tasks)
+            })
+            .filter(|lane| lane.columns.iter().any(|(_, tasks)| !tasks.is_empty()))
+            .collect()
+    } else {
+        vec![lane(None, &tasks.iter().collect::<Vec<_>>())]
+    };
+
+    let rendered = template.render(context! {
+        title => "Tableau kanban",
+        lanes => lanes,
+        by_project => by_project,
+        projects => Project::by_id(&conn)?,
+        all_status => Status::all(),
+        kanban => true,
+    })?;
+    Ok(Html(rendered))
+}
+
+// carte d'une tâche du tableau kanban, rechargée par la page à chaque modification
+
+pub async fn kanban_card(
+    Path(id): Path<u32>,
+    State(state): State<Arc<AppState>>,
+    param: Result<Query<KanbanParam>, QueryRejection>,
+) -> AppResult<Html<String>> {
+    let Query(param) = param?;
+    let template = state.env.get_template("dashboard.card")?;
+
+    let conn = state.db.lock().unwrap();
+
+    let task = Task::get_by_id(id, &conn)?;
+    if task.deleted_at.is_some() {
+        return Err(AppError::NotFound);
+    }
+    let rendered = template.render(context! {
+        by_project => param.lanes == "project",
+        task => task,
+        projects => Project::by_id(&conn)?,
+        all_status => Status::all(),
+        kanban => true,
+    })?;
+    Ok(Html(rendered))
+}
@@ -1,19 +1,17 @@
 use axum::extract::State;
-use axum::http::StatusCode;
 use axum::response::Html;
 use minijinja::context;
 use std::sync::Arc;
 
 use crate::AppState;
+use crate::error::AppResult;
 
-pub async fn controller_home(State(state): State<Arc<AppState>>) -> Result<Html<String>, StatusCode> {
-    let template = state.env.get_template("home").unwrap();
+pub async fn controller_home(State(state): State<Arc<AppState>>) -> AppResult<Html<String>> {
+    let template = state.env.get_template("home")?;
 
-    let rendered = template
-        .render(context! {
-            title => "Todo liste",
-        })
-        .unwrap();
+    let rendered = template.render(context! {
+        title => "Todo liste",
+    })?;
 
     Ok(Html(rendered))
 }
\ No newline at end of file
@@ -1,2 +1,13 @@
+pub mod api;
+pub mod backup;
+pub mod calendar;
+pub mod csv_transfer;
+pub mod dashboard;
 pub mod home;
-pub mod task;
\ No newline at end of file
+pub mod plain_text;
+pub mod project;
+pub mod settings;
+pub mod stats;
+pub mod task;
+pub mod timer;
+pub mod ws;
\ No newline at end of file
@@ -0,0 +1,124 @@
+use crate::AppState;
+use crate::controllers::ws::TaskEvent;
+use crate::error::{AppError, AppResult};
+use crate::models::markdown;
+use crate::models::tag::{Tag, has_all_tags, parse_tags};
+use crate::models::task::{Filter, Task};
+use crate::models::todo_txt;
+use axum::extract::rejection::{FormRejection, QueryRejection};
+use axum::extract::{Form, Query, State};
+use axum::http::header;
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use minijinja::context;
+use rusqlite::Connection;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// Échanges au format texte : todo.txt (export et import) et liste Markdown (export)
+
+// structure pour récupérer le filtre des tâches à exporter
+
+#[derive(Deserialize)]
+pub struct ExportParam {
+    filter: Option<Filter>,
+    // étiquettes que doivent toutes porter les tâches, séparées par des virgules
+    #[serde(default)]
+    tags: String,
+}
+
+// structure pour récupérer le fichier todo.txt collé ou chargé dans le formulaire
+
+#[derive(Deserialize)]
+pub struct ImportForm {
+    content: String,
+}
+
+// tâches du filtre portant toutes les étiquettes demandées
+
+fn export_tasks(filter: &Filter, tags: &str, task_tags: &HashMap<u32, Vec<String>>, conn: &Connection) -> AppResult<Vec<Task>> {
+    let tags = parse_tags(tags);
+    let mut tasks = Task::get_with_filter(conn, filter)?;
+    tasks.retain(|task| has_all_tags(task_tags.get(&task.id), &tags));
+    Ok(tasks)
+}
+
+fn attachment(content_type: &str, filename: &str, content: String) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, format!("{content_type}; charset=utf-8")),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}\"")),
+        ],
+        content,
+    )
+        .into_response()
+}
+
+// exporte les tâches d'un filtre au format todo.txt
+
+pub async fn export_todo_txt(
+    param: Result<Query<ExportParam>, QueryRejection>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Response> {
+    let Query(param) = param?;
+    let conn = state.db.lock().unwrap();
+    let filter = param.filter.unwrap_or(Filter::All);
+    let task_tags = Tag::by_task(&conn)?;
+    let tasks = export_tasks(&filter, &param.tags, &task_tags, &conn)?;
+    Ok(attachment("text/plain", "todo.txt", todo_txt::export(&tasks, &task_tags)))
+}
+
+// exporte les tâches d'un filtre en liste Markdown groupée par projet
+
+pub async fn export_markdown(
+    param: Result<Query<ExportParam>, QueryRejection>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Response> {
+    let Query(param) = param?;
+    let conn = state.db.lock().unwrap();
+    let filter = param.filter.unwrap_or(Filter::All);
+    let task_tags = Tag::by_task(&conn)?;
+    let tasks = export_tasks(&filter, &param.tags, &task_tags, &conn)?;
+    let filename = format!("taches-{filter:?}.md");
+    Ok(attachment("text/markdown", &filename, markdown::export(&filter.to_string(), tasks, &task_tags)))
+}
+
+// affiche le formulaire d'import, avec les erreurs du fichier refusé
+
+fn render_import(state: &AppState, content: &str, errors: &[String]) -> AppResult<Html<String>> {
+    let template = state.env.get_template("todo_txt.import")?;
+    let rendered = template.render(context! {
+        title => "Import todo.txt",
+        content => content,
+        errors => errors,
+    })?;
+    Ok(Html(rendered))
+}
+
+// retourne le formulaire d'import
+
+pub async fn import_form(State(state): State<Arc<AppState>>) -> AppResult<Html<String>> {
+    render_import(&state, "", &[])
+}
+
+// importe les tâches du fichier todo.txt
+
+pub async fn import(
+    State(state): State<Arc<AppState>>,
+    input: Result<Form<ImportForm>, FormRejection>,
+) -> AppResult<Redirect> {
+    let Form(input) = input?;
+    let imported = {
+        let conn = state.db.lock().unwrap();
+        todo_txt::import(&input.content, &conn)?
+    };
+    match imported {
+        Ok(tasks) => {
+            for task in tasks {
+                state.publish(TaskEvent::Created { task });
+            }
+            Ok(Redirect::to("/task"))
+        }
+        Err(errors) => Err(AppError::InvalidForm(render_import(&state, &input.content, &errors)?)),
+    }
+}
@@ -0,0 +1,169 @@
+use crate::AppState;
+use crate::error::{AppError, AppResult};
+use crate::models::project::Project;
+use crate::models::tag::Tag;
+use crate::models::task::{Duration, Importance, Priority, Status, Task};
+use crate::models::time_entry::TimeEntry;
+use axum::extract::rejection::FormRejection;
+use axum::extract::{Form, Path, State};
+use axum::response::{Html, Redirect};
+use minijinja::context;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// Structure pour récupérer les données du formulaire html de création et d'édition de projet
+
+#[derive(Deserialize, Debug)]
+pub struct ProjectForm {
+    name: String,
+    color: String,
+    #[serde(default)]
+    description: String,
+    // case à cocher : absente du formulaire quand elle n'est pas cochée
+    #[serde(default)]
+    archived: Option<String>,
+}
+
+impl ProjectForm {
+    fn to_project(&self) -> Project {
+        Project {
+            name: self.name.trim().to_string(),
+            color: self.color.clone(),
+            description: self.description.clone(),
+            archived: self.archived.is_some(),
+            ..Project::default()
+        }
+    }
+}
+
+// La couleur vient d'un champ de type color : #rrggbb
+
+fn is_valid_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// Vérifie le projet saisi, retourne le message d'erreur à afficher
+
+fn check_project(project: &Project, id: Option<u32>, state: &AppState) -> AppResult<Option<String>> {
+    if project.name.is_empty() {
+        return Ok(Some("Le nom du projet est obligatoire".to_string()));
+    }
+    if !is_valid_color(&project.color) {
+        return Ok(Some(format!("Couleur invalide : {}", project.color)));
+    }
+    let conn = state.db.lock().unwrap();
+    if Project::name_taken(&project.name, id, &conn)? {
+        return Ok(Some(format!("Un projet nommé « {} » existe déjà", project.name)));
+    }
+    Ok(None)
+}
+
+// affiche le formulaire de projet, avec un éventuel message d'erreur
+
+fn render_form(state: &AppState, project: &Project, error: Option<&str>) -> AppResult<Html<String>> {
+    let template = state.env.get_template("project.edit")?;
+    let rendered = template.render(context! {
+        title => if project.id == 0 { "Nouveau projet".to_string() } else { project.name.clone() },
+        project => project,
+        error => error
+    })?;
+    Ok(Html(rendered))
+}
+
+// liste des projets avec leur nombre de tâches ouvertes
+
+pub async fn index(State(state): State<Arc<AppState>>) -> AppResult<Html<String>> {
+    let template = state.env.get_template("project.index")?;
+
+    let conn = state.db.lock().unwrap();
+
+    let rendered = template.render(context! {
+        title => "Projets",
+        projects => Project::get_all(true, &conn)?,
+        open_counts => Project::open_counts(&conn)?,
+    })?;
+    Ok(Html(rendered))
+}
+
+// page d'un projet : nombre de tâches par statut et liste des tâches
+
+pub async fn show(Path(id): Path<u32>, State(state): State<Arc<AppState>>) -> AppResult<Html<String>> {
+    let template = state.env.get_template("project.show")?;
+
+    let conn = state.db.lock().unwrap();
+
+    let project = Project::get_by_id(id, &conn)?;
+    let tasks = Task::get_by_project(id, &conn)?;
+
+    let rendered = template.render(context! {
+        title => project.name.clone(),
+        project => project,
+        counts => Project::status_counts(id, &conn)?,
+        nb => tasks.len(),
+        tasks => tasks,
+        depths => HashMap::<u32, usize>::new(),
+        task_tags => Tag::by_task(&conn)?,
+        progress => Task::children_progress(&conn)?,
+        waiting => Task::pending_blockers(&conn)?,
+        projects => Project::by_id(&conn)?,
+        time_spent => TimeEntry::totals(&conn)?,
+        running => TimeEntry::running(&conn)?,
+        all_priority => Priority::all(),
+        all_importance => Importance::all(),
+        all_duration => Duration::all(),
+        all_status => Status::all(),
+    })?;
+    Ok(Html(rendered))
+}
+
+// retourne le formulaire de création de projet
+
+pub async fn create(State(state): State<Arc<AppState>>) -> AppResult<Html<String>> {
+    render_form(&state, &Project::default(), None)
+}
+
+// ajoute un nouveau projet en base
+
+pub async fn insert(
+    State(state): State<Arc<AppState>>,
+    input: Result<Form<ProjectForm>, FormRejection>,
+) -> AppResult<Redirect> {
+    let Form(input) = input?;
+    let mut project = input.to_project();
+    if let Some(message) = check_project(&project, None, &state)? {
+        return Err(AppError::InvalidForm(render_form(&state, &project, Some(&message))?));
+    }
+    let conn = state.db.lock().unwrap();
+    project.insert(&conn)?;
+    Ok(Redirect::to(&format!("/project/{}", project.id)))
+}
+
+// retourne le formulaire de mise à jour d'un projet
+
+pub async fn edit(Path(id): Path<u32>, State(state): State<Arc<AppState>>) -> AppResult<Html<String>> {
+    let project = {
+        let conn = state.db.lock().unwrap();
+        Project::get_by_id(id, &conn)?
+    };
+    render_form(&state, &project, None)
+}
+
+// met à jour un projet et renvoie sur sa page
+
+pub async fn update(
+    Path(id): Path<u32>,
+    State(state): State<Arc<AppState>>,
+    input: Result<Form<ProjectForm>, FormRejection>,
+) -> AppResult<Redirect> {
+    let Form(input) = input?;
+    let mut project = Project { id, ..input.to_project() };
+    if let Some(message) = check_project(&project, Some(id), &state)? {
+        return Err(AppError::InvalidForm(render_form(&state, &project, Some(&message))?));
+    }
+    let conn = state.db.lock().unwrap();
+    match project.update(id, &conn)? {
+        0 => Err(AppError::NotFound),
+        _ => Ok(Redirect::to(&format!("/project/{id}"))),
+    }
+}
@@ -0,0 +1,85 @@
+use crate::AppState;
+use crate::error::{AppError, AppResult};
+use crate::models::scoring::{Factor, MAX_POINTS, ScoringProfile};
+use axum::extract::rejection::{FormRejection, QueryRejection};
+use axum::extract::{Form, Query, State};
+use axum::response::{Html, Redirect};
+use minijinja::context;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// Paramètre ajouté après l'enregistrement : nombre de tâches dont le score a changé
+
+#[derive(Deserialize, Debug)]
+pub struct ScoringParam {
+    rescored: Option<usize>,
+}
+
+// affiche la page des poids du score, avec un éventuel message
+
+fn render_scoring(state: &AppState, profile: &ScoringProfile, message: Option<String>, error: Option<&str>) -> AppResult<Html<String>> {
+    let template = state.env.get_template("settings.scoring")?;
+    let rendered = template.render(context! {
+        title => "Calcul du score",
+        profile => profile,
+        all_factor => Factor::all(),
+        max_points => MAX_POINTS,
+        message => message,
+        error => error
+    })?;
+    Ok(Html(rendered))
+}
+
+// page de paramétrage des poids du score
+
+pub async fn scoring(
+    State(state): State<Arc<AppState>>,
+    param: Result<Query<ScoringParam>, QueryRejection>,
+) -> AppResult<Html<String>> {
+    let Query(param) = param?;
+    let profile = {
+        let conn = state.db.lock().unwrap();
+        ScoringProfile::load(&conn)?
+    };
+    let message = param.rescored.map(|nb| format!("Profil enregistré, {nb} tâche(s) recalculée(s)"));
+    render_scoring(&state, &profile, message, None)
+}
+
+// enregistre les poids saisis (champs nommés critère.valeur) et recalcule les scores
+
+pub async fn save_scoring(
+    State(state): State<Arc<AppState>>,
+    input: Result<Form<HashMap<String, String>>, FormRejection>,
+) -> AppResult<Redirect> {
+    let Form(input) = input?;
+    let mut profile = ScoringProfile::default();
+    for weight in profile.weights.iter_mut() {
+        let key = format!("{:?}.{}", weight.factor, weight.variant);
+        let Some(value) = input.get(&key) else { continue };
+        match value.trim().parse() {
+            Ok(points) if ScoringProfile::valid_points(points) => weight.points = points,
+            Ok(_) => {
+                let message = format!("{} : les points doivent être compris entre -{MAX_POINTS} et {MAX_POINTS}", weight.label);
+                return Err(AppError::InvalidForm(render_scoring(&state, &profile, None, Some(&message))?));
+            }
+            Err(_) => {
+                let message = format!("{} : nombre invalide « {value} »", weight.label);
+                return Err(AppError::InvalidForm(render_scoring(&state, &profile, None, Some(&message))?));
+            }
+        }
+    }
+    let conn = state.db.lock().unwrap();
+    profile.save(&conn)?;
+    let nb = state.rescore_all(&conn)?;
+    Ok(Redirect::to(&format!("/settings/scoring?rescored={nb}")))
+}
+
+// revient aux poids par défaut et recalcule les scores
+
+pub async fn reset_scoring(State(state): State<Arc<AppState>>) -> AppResult<Redirect> {
+    let conn = state.db.lock().unwrap();
+    ScoringProfile::default().save(&conn)?;
+    let nb = state.rescore_all(&conn)?;
+    Ok(Redirect::to(&format!("/settings/scoring?rescored={nb}")))
+}
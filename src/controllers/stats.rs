@@ -0,0 +1,99 @@
+use crate::AppState;
+use crate::error::{AppError, AppResult};
+use crate::models::stats::{LeadTime, Stats, period};
+use crate::models::task::Status;
+use crate::utils::parse_optional_date;
+use crate::views::chart::{self, Series};
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{Query, State};
+use axum::response::Html;
+use chrono::NaiveDate;
+use minijinja::context;
+use serde::Deserialize;
+use std::sync::Arc;
+
+// Période de la page de statistiques (champs date du formulaire, vides par défaut)
+
+#[derive(Deserialize, Debug)]
+pub struct StatsParam {
+    #[serde(default)]
+    from: String,
+    #[serde(default)]
+    to: String,
+}
+
+// Couleurs des graphiques, celles des boutons de statut de la liste des tâches
+
+const LEAD_COLOR: &str = "#0d6efd";
+const CYCLE_COLOR: &str = "#fd7e14";
+
+fn status_color(status: Status) -> &'static str {
+    match status {
+        Status::ToDo => "#6c757d",
+        Status::InProgress => "#ffc107",
+        Status::Blocked => "#dc3545",
+        Status::Finished => "#198754",
+        Status::Canceled => "#adb5bd",
+    }
+}
+
+// graphiques de la page : tâches terminées par jour et par semaine, délais, flux cumulé
+fn charts(stats: &Stats) -> Vec<(&'static str, String)> {
+    let day_labels: Vec<String> = stats.daily.iter().map(|(day, _)| day.format("%d/%m").to_string()).collect();
+    let week_labels: Vec<String> = stats.weekly.iter().map(|(week, _)| week.format("%d/%m").to_string()).collect();
+    let done = |values: &[(NaiveDate, u32)]| vec![Series {
+        label: "Terminées",
+        color: status_color(Status::Finished),
+        values: values.iter().map(|(_, count)| f64::from(*count)).collect(),
+    }];
+    let lead_times = |groups: &[LeadTime]| {
+        let labels: Vec<String> = groups.iter().map(|group| group.label.clone()).collect();
+        chart::bars(&labels, &[
+            Series { label: "Création → fin", color: LEAD_COLOR, values: groups.iter().map(|group| group.lead_time.unwrap_or_default()).collect() },
+            Series { label: "Début → fin", color: CYCLE_COLOR, values: groups.iter().map(|group| group.cycle_time.unwrap_or_default()).collect() },
+        ])
+    };
+    let flow_labels: Vec<String> = stats.flow_dates.iter().map(|day| day.format("%d/%m").to_string()).collect();
+    let flow: Vec<Series> = stats
+        .flow
+        .iter()
+        .map(|serie| Series {
+            label: &serie.label,
+            color: status_color(serie.status),
+            values: serie.counts.iter().map(|count| f64::from(*count)).collect(),
+        })
+        .collect();
+    vec![
+        ("Tâches terminées par jour", chart::bars(&day_labels, &done(&stats.daily))),
+        ("Tâches terminées par semaine", chart::bars(&week_labels, &done(&stats.weekly))),
+        ("Délais moyens par priorité (jours)", lead_times(&stats.by_priority)),
+        ("Délais moyens par durée (jours)", lead_times(&stats.by_duration)),
+        ("Délais moyens par projet (jours)", lead_times(&stats.by_project)),
+        ("Flux cumulé par statut", chart::stacked_areas(&flow_labels, &flow)),
+    ]
+}
+
+// page de statistiques sur une période, les 30 derniers jours par défaut
+
+pub async fn index(
+    State(state): State<Arc<AppState>>,
+    param: Result<Query<StatsParam>, QueryRejection>,
+) -> AppResult<Html<String>> {
+    let Query(param) = param?;
+    let invalid = |_| AppError::BadRequest("Date invalide".to_string());
+    let (from, to) = period(parse_optional_date(&param.from).map_err(invalid)?, parse_optional_date(&param.to).map_err(invalid)?)
+        .ok_or_else(|| AppError::BadRequest("Période invalide : le début doit précéder la fin, sur un an au plus".to_string()))?;
+    let template = state.env.get_template("stats.index")?;
+
+    let stats = {
+        let conn = state.db.lock().unwrap();
+        Stats::compute(from, to, &conn)?
+    };
+
+    let rendered = template.render(context! {
+        title => "Statistiques",
+        charts => charts(&stats),
+        stats => stats,
+    })?;
+    Ok(Html(rendered))
+}
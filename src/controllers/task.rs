@@ -1,18 +1,135 @@
 use crate::AppState;
-use crate::models::task::{Duration, Filter, Importance, Priority, Status, Task};
-use crate::utils::parse_optional_date;
+use crate::controllers::ws::TaskEvent;
+use crate::error::{AppError, AppResult};
+use crate::models::history::{EventKind, HistoryEntry};
+use crate::models::note::Note;
+use crate::models::project::Project;
+use crate::models::recurrence::Recurrence;
+use crate::models::savepoint::Savepoint;
+use crate::models::scoring::{Factor, ScoringProfile};
+use crate::models::tag::{Tag, has_all_tags, parse_tags};
+use crate::models::task::{Duration, Filter, Importance, Priority, SearchHit, Status, Task, tree_order};
+use crate::models::time_entry::TimeEntry;
+use crate::utils::{parse_optional_date, parse_optional_time};
+use axum::extract::rejection::{FormRejection, QueryRejection};
 use axum::extract::{Form, Path, Query, State};
-use axum::{
-    http::StatusCode,
-    response::{Html, Redirect},
-};
-use chrono::NaiveDate;
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use chrono::{Local, NaiveDate, NaiveTime};
 use minijinja::context;
+use rusqlite::Connection;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 pub trait ToTask {
-    fn to_task(&self) -> Task;
+    fn to_task(&self) -> AppResult<Task>;
+}
+
+// Convertit un champ date du formulaire, l'erreur indique le libellé du champ en cause
+
+fn parse_date_field(label: &str, value: &str) -> AppResult<Option<NaiveDate>> {
+    parse_optional_date(value)
+        .map_err(|_| AppError::BadRequest(format!("Date invalide pour « {label} » : {value}")))
+}
+
+// Convertit le champ heure d'échéance du formulaire
+
+fn parse_time_field(value: &str) -> AppResult<Option<NaiveTime>> {
+    parse_optional_time(value)
+        .map_err(|_| AppError::BadRequest(format!("Heure invalide pour « Heure limite » : {value}")))
+}
+
+// Convertit le champ récurrence du formulaire (vide : tâche non récurrente)
+
+fn parse_recurrence_field(value: &str) -> AppResult<Option<Recurrence>> {
+    match value.trim() {
+        "" => Ok(None),
+        value => value.parse().map(Some).map_err(AppError::BadRequest),
+    }
+}
+
+// Convertit le champ tâche parente du formulaire (vide : pas de parente)
+
+fn parse_parent_field(value: &str) -> AppResult<Option<u32>> {
+    match value.trim() {
+        "" => Ok(None),
+        value => value
+            .parse()
+            .map(Some)
+            .map_err(|_| AppError::BadRequest(format!("Tâche parente invalide : {value}"))),
+    }
+}
+
+// Convertit le champ projet du formulaire (vide : pas de projet)
+
+fn parse_project_field(value: &str) -> AppResult<Option<u32>> {
+    match value.trim() {
+        "" => Ok(None),
+        value => value
+            .parse()
+            .map(Some)
+            .map_err(|_| AppError::BadRequest(format!("Projet invalide : {value}"))),
+    }
+}
+
+// Vérifie que le projet choisi existe
+
+fn check_project(project_id: Option<u32>, conn: &Connection) -> AppResult<()> {
+    match project_id {
+        Some(project_id) => match Project::get_by_id(project_id, conn) {
+            Ok(_) => Ok(()),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Err(AppError::BadRequest("Le projet choisi n'existe pas".to_string())),
+            Err(err) => Err(err.into()),
+        },
+        None => Ok(()),
+    }
+}
+
+// Vérifie que la tâche parente choisie est acceptable (existe et ne crée pas de boucle)
+
+fn check_parent(id: Option<u32>, parent_id: Option<u32>, conn: &Connection) -> AppResult<()> {
+    match parent_id {
+        Some(parent_id) if !Task::can_have_parent(id, parent_id, conn)? => Err(AppError::BadRequest(
+            "La tâche parente doit exister et ne peut être ni la tâche elle-même ni une de ses sous-tâches".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+// Tâche qui peut être modifiée : une tâche à la corbeille est traitée comme introuvable
+
+fn get_active(id: u32, conn: &Connection) -> AppResult<Task> {
+    match Task::get_by_id(id, conn)? {
+        task if task.deleted_at.is_some() => Err(AppError::NotFound),
+        task => Ok(task),
+    }
+}
+
+// Une tâche ne peut pas être terminée tant que ses sous-tâches ne le sont pas
+
+fn check_children_finished(id: u32, status: Status, conn: &Connection) -> AppResult<Vec<Task>> {
+    if status != Status::Finished {
+        return Ok(vec![]);
+    }
+    Ok(Task::open_children(id, conn)?)
+}
+
+// Tâches qui peuvent être choisies comme parente, indentées selon la hiérarchie
+
+fn parent_choices(id: Option<u32>, conn: &Connection) -> AppResult<Vec<(u32, String)>> {
+    let excluded = match id {
+        Some(id) => Task::descendant_ids(id, conn)?,
+        None => vec![],
+    };
+    let tasks = Task::get_with_filter(conn, &Filter::DailyWork)?
+        .into_iter()
+        .filter(|task| !excluded.contains(&task.id))
+        .collect();
+    Ok(tree_order(tasks)
+        .into_iter()
+        .map(|(task, depth)| (task.id, format!("{}{}", "— ".repeat(depth), task.description)))
+        .collect())
 }
 
 // Structure pour récupérer les données du formulaire html de création de tâche
@@ -25,20 +142,37 @@ pub struct CreateTaskForm {
     importance: Importance,
     duration: Duration,
     status: Status,
-    grouping: String,
+    #[serde(default)]
+    project_id: String,
+    #[serde(default)]
+    parent_id: String,
+    // étiquettes séparées par des virgules
+    #[serde(default)]
+    tags: String,
+    #[serde(default)]
+    due_date: String,
+    #[serde(default)]
+    due_time: String,
+    // règle de récurrence, ex : weekly:mon,thu
+    #[serde(default)]
+    recurrence: String,
 }
 
 impl ToTask for CreateTaskForm {
-    fn to_task(&self) -> Task {
-        Task {
+    fn to_task(&self) -> AppResult<Task> {
+        Ok(Task {
             description: self.description.clone(),
             priority: self.priority,
             importance: self.importance,
             duration: self.duration,
             status: self.status,
-            grouping: self.grouping.clone(),
+            project_id: parse_project_field(&self.project_id)?,
+            parent_id: parse_parent_field(&self.parent_id)?,
+            due_date: parse_date_field("Échéance", &self.due_date)?,
+            due_time: parse_time_field(&self.due_time)?,
+            recurrence: parse_recurrence_field(&self.recurrence)?,
             ..Task::default()
-        }
+        })
     }
 }
 
@@ -52,36 +186,81 @@ pub struct EditTaskForm {
     importance: Importance,
     duration: Duration,
     status: Status,
-    grouping: String,
-    creation_date: NaiveDate,
+    #[serde(default)]
+    project_id: String,
+    creation_date: String,
     completion_date: String,
     start_date: String,
-    scoring: u8,
+    scoring: i64,
+    #[serde(default)]
+    parent_id: String,
+    // étiquettes séparées par des virgules
+    #[serde(default)]
+    tags: String,
+    #[serde(default)]
+    due_date: String,
+    #[serde(default)]
+    due_time: String,
+    // règle de récurrence, ex : weekly:mon,thu
+    #[serde(default)]
+    recurrence: String,
+    #[serde(default)]
+    note: Option<String>,
 }
 
-impl ToTask for EditTaskForm {
-    fn to_task(&self) -> Task {
+impl EditTaskForm {
+    // tâche construite avec les seuls champs valides, pour réafficher le formulaire en erreur
+    fn draft(&self, id: u32) -> Task {
         Task {
+            id,
             description: self.description.clone(),
             priority: self.priority,
             importance: self.importance,
             duration: self.duration,
             status: self.status,
-            grouping: self.grouping.clone(),
-            completion_date: parse_optional_date(&self.completion_date).unwrap(),
-            start_date: parse_optional_date(&self.start_date).unwrap(),
-            creation_date: self.creation_date,
+            project_id: parse_project_field(&self.project_id).unwrap_or(None),
+            completion_date: parse_optional_date(&self.completion_date).unwrap_or(None),
+            start_date: parse_optional_date(&self.start_date).unwrap_or(None),
+            creation_date: parse_optional_date(&self.creation_date)
+                .ok()
+                .flatten()
+                .unwrap_or(Local::now().date_naive()),
             scoring: self.scoring,
+            parent_id: parse_parent_field(&self.parent_id).unwrap_or(None),
+            due_date: parse_optional_date(&self.due_date).unwrap_or(None),
+            due_time: parse_optional_time(&self.due_time).unwrap_or(None),
+            recurrence: parse_recurrence_field(&self.recurrence).unwrap_or(None),
             ..Task::default()
         }
     }
 }
 
+impl ToTask for EditTaskForm {
+    fn to_task(&self) -> AppResult<Task> {
+        Ok(Task {
+            completion_date: parse_date_field("Terminé le", &self.completion_date)?,
+            start_date: parse_date_field("Commencé le", &self.start_date)?,
+            creation_date: parse_date_field("Créé le", &self.creation_date)?.ok_or_else(|| {
+                AppError::BadRequest("La date de création est obligatoire".to_string())
+            })?,
+            parent_id: parse_parent_field(&self.parent_id)?,
+            project_id: parse_project_field(&self.project_id)?,
+            due_date: parse_date_field("Échéance", &self.due_date)?,
+            due_time: parse_time_field(&self.due_time)?,
+            recurrence: parse_recurrence_field(&self.recurrence)?,
+            ..self.draft(0)
+        })
+    }
+}
+
 // structure pour récupérer les paramètres url de changement de statut
 
 #[derive(Deserialize)]
 pub struct StatusParam {
     status: Status,
+    // terminer aussi les sous-tâches encore ouvertes
+    #[serde(default)]
+    cascade: bool,
 }
 
 // structure pour récupérer les paramètres url de filtre de liste
@@ -89,6 +268,33 @@ pub struct StatusParam {
 #[derive(Deserialize)]
 pub struct FilterParam {
     filter: Filter,
+    // étiquettes que doivent toutes porter les tâches, séparées par des virgules
+    #[serde(default)]
+    tags: String,
+}
+
+// structure pour récupérer la tâche bloquante choisie sur la page d'édition
+
+#[derive(Deserialize)]
+pub struct BlockerForm {
+    blocker_id: u32,
+}
+
+// structure pour récupérer le filtre optionnel de la page qui demande une ligne
+
+#[derive(Deserialize)]
+pub struct RowParam {
+    filter: Option<Filter>,
+    #[serde(default)]
+    tags: String,
+}
+
+// structure pour récupérer le terme saisi dans la barre de recherche
+
+#[derive(Deserialize)]
+pub struct SearchParam {
+    #[serde(default)]
+    search_term: String,
 }
 
 // structure pour récupérer les paramètres url de focus de liste
@@ -98,85 +304,268 @@ pub struct FocusParam {
     id: u32,
 }
 
-fn do_filter(filter: Filter, id: Option<u32>, state: Arc<AppState>) -> Html<String> {
-    let template = state.env.get_template("task.index").unwrap();
+// Tâches du filtre portant toutes les étiquettes demandées, dans l'ordre d'affichage
 
-    let conn = state.db.lock().unwrap();
+fn filter_tasks(filter: &Filter, tags: &[String], task_tags: &HashMap<u32, Vec<String>>, conn: &Connection) -> AppResult<Vec<(Task, usize)>> {
+    let mut tasks = Task::get_with_filter(conn, filter)?;
+    tasks.retain(|task| has_all_tags(task_tags.get(&task.id), tags));
+    Ok(tree_order(tasks))
+}
 
-    let tasks = Task::get_with_filter(&conn, &filter)
-        .map_err(|err| {
-            eprintln!("Erreur sql: {:?}", err);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })
-        .unwrap();
-
-    let rendered = template
-        .render(context! {
-            title => "Todo liste",
-            filter => filter.to_string(),
-            nb => tasks.len(),
-            tasks => tasks,
-            all_priority => Priority::all(),
-            all_importance => Importance::all(),
-            all_duration => Duration::all(),
-            all_status => Status::all(),
-            id => id
+// Pour chaque étiquette, si elle est sélectionnée et la liste d'étiquettes du lien qui
+// l'ajoute ou la retire de la sélection
+
+fn tag_links(selected: &[String], conn: &Connection) -> AppResult<Vec<(String, bool, String)>> {
+    Ok(Tag::get_all(conn)?
+        .into_iter()
+        .map(|tag| {
+            let same = |name: &String| name.to_lowercase() == tag.name.to_lowercase();
+            let is_selected = selected.iter().any(same);
+            let mut toggled: Vec<String> = selected.iter().filter(|name| !same(name)).cloned().collect();
+            if !is_selected {
+                toggled.push(tag.name.clone());
+            }
+            (tag.name, is_selected, toggled.join(","))
         })
-        .unwrap();
-    Html(rendered)
+        .collect())
+}
+
+fn do_filter(filter: Filter, id: Option<u32>, tags: Vec<String>, state: Arc<AppState>) -> AppResult<Html<String>> {
+    let template = state.env.get_template("task.index")?;
+
+    let conn = state.db.lock().unwrap();
+
+    let task_tags = Tag::by_task(&conn)?;
+    let ordered = filter_tasks(&filter, &tags, &task_tags, &conn)?;
+    let depths: HashMap<u32, usize> = ordered.iter().map(|(task, depth)| (task.id, *depth)).collect();
+    let tasks: Vec<Task> = ordered.into_iter().map(|(task, _)| task).collect();
+
+    let rendered = template.render(context! {
+        title => "Todo liste",
+        filter => filter.to_string(),
+        filter_name => filter,
+        live => true,
+        selected_tags => tags.join(","),
+        tag_links => tag_links(&tags, &conn)?,
+        task_tags => task_tags,
+        nb => tasks.len(),
+        tasks => tasks,
+        depths => depths,
+        progress => Task::children_progress(&conn)?,
+        waiting => Task::pending_blockers(&conn)?,
+        projects => Project::by_id(&conn)?,
+        time_spent => TimeEntry::totals(&conn)?,
+        running => TimeEntry::running(&conn)?,
+        all_priority => Priority::all(),
+        all_importance => Importance::all(),
+        all_duration => Duration::all(),
+        all_status => Status::all(),
+        id => id
+    })?;
+    Ok(Html(rendered))
 }
 
 pub async fn filter(
-    Query(param): Query<FilterParam>,
+    param: Result<Query<FilterParam>, QueryRejection>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Html<String>> {
+    let Query(param) = param?;
+    do_filter(param.filter, None, parse_tags(&param.tags), state)
+}
+
+// recherche plein texte, les résultats sont affichés par pertinence avec les termes surlignés
+
+pub async fn search(
+    param: Result<Query<SearchParam>, QueryRejection>,
     State(state): State<Arc<AppState>>,
-) -> Result<Html<String>, StatusCode> {
-    Ok(do_filter(param.filter, None, state))
+) -> AppResult<Html<String>> {
+    let Query(param) = param?;
+    let template = state.env.get_template("task.index")?;
+
+    let conn = state.db.lock().unwrap();
+
+    let hits = Task::search(&conn, &param.search_term)?;
+    let tasks: Vec<&Task> = hits.iter().map(|hit| &hit.task).collect();
+    let highlights: HashMap<u32, &SearchHit> = hits.iter().map(|hit| (hit.task.id, hit)).collect();
+
+    let rendered = template.render(context! {
+        title => "Todo liste",
+        filter => format!("Recherche « {} »", param.search_term.trim()),
+        nb => tasks.len(),
+        tasks => tasks,
+        highlights => highlights,
+        task_tags => Tag::by_task(&conn)?,
+        waiting => Task::pending_blockers(&conn)?,
+        projects => Project::by_id(&conn)?,
+        time_spent => TimeEntry::totals(&conn)?,
+        running => TimeEntry::running(&conn)?,
+        live => false,
+        search_term => param.search_term,
+        all_priority => Priority::all(),
+        all_importance => Importance::all(),
+        all_duration => Duration::all(),
+        all_status => Status::all(),
+    })?;
+    Ok(Html(rendered))
 }
 
 // retourne toutes les tâches
 
-pub async fn index(State(state): State<Arc<AppState>>) -> Result<Html<String>, StatusCode> {
-    Ok(do_filter(Filter::All, None, state))
+pub async fn index(State(state): State<Arc<AppState>>) -> AppResult<Html<String>> {
+    do_filter(Filter::All, None, vec![], state)
 }
 
 // retourne toutes les tâches avec un focus sur une tâche en particulier
 
 pub async fn focus(
-    Query(param): Query<FocusParam>,
+    param: Result<Query<FocusParam>, QueryRejection>,
     State(state): State<Arc<AppState>>,
-) -> Result<Html<String>, StatusCode> {
-    Ok(do_filter(Filter::All, Some(param.id), state))
+) -> AppResult<Html<String>> {
+    let Query(param) = param?;
+    do_filter(Filter::All, Some(param.id), vec![], state)
 }
 
 // retourne le formulaire de création de tache
 
-pub async fn create(State(state): State<Arc<AppState>>) -> Result<Html<String>, StatusCode> {
-    let template = state.env.get_template("task.create").unwrap();
-
-    let rendered = template
-        .render(context! {
-            all_priority => Priority::all(),
-            all_importance => Importance::all(),
-            all_duration => Duration::all(),
-            all_status => Status::all(),
-        })
-        .unwrap();
+pub async fn create(State(state): State<Arc<AppState>>) -> AppResult<Html<String>> {
+    let template = state.env.get_template("task.create")?;
+
+    let (parents, projects, all_tags) = {
+        let conn = state.db.lock().unwrap();
+        (parent_choices(None, &conn)?, Project::get_all(false, &conn)?, Tag::get_all(&conn)?)
+    };
+
+    let rendered = template.render(context! {
+        parents => parents,
+        projects => projects,
+        all_tags => all_tags,
+        all_priority => Priority::all(),
+        all_importance => Importance::all(),
+        all_duration => Duration::all(),
+        all_status => Status::all(),
+    })?;
     Ok(Html(rendered))
 }
 
+// retourne la ligne html d'une tâche pour la mise à jour en direct de la liste,
+// 204 si la tâche ne fait pas partie du filtre de la page
+
+pub async fn row(
+    Path(id): Path<u32>,
+    param: Result<Query<RowParam>, QueryRejection>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Response> {
+    let Query(param) = param?;
+    let template = state.env.get_template("task.row")?;
+
+    let conn = state.db.lock().unwrap();
+
+    let task_tags = Tag::by_task(&conn)?;
+    let task = match param.filter {
+        Some(filter) => filter_tasks(&filter, &parse_tags(&param.tags), &task_tags, &conn)?.into_iter().find(|(task, _)| task.id == id),
+        None => Some((get_active(id, &conn)?, 0)),
+    };
+    let Some((task, depth)) = task else {
+        return Ok(StatusCode::NO_CONTENT.into_response());
+    };
+
+    let rendered = template.render(context! {
+        depths => HashMap::from([(task.id, depth)]),
+        filter_name => param.filter,
+        task_tags => task_tags,
+        progress => Task::children_progress(&conn)?,
+        waiting => Task::pending_blockers(&conn)?,
+        projects => Project::by_id(&conn)?,
+        time_spent => TimeEntry::totals(&conn)?,
+        running => TimeEntry::running(&conn)?,
+        task => task,
+        all_priority => Priority::all(),
+        all_importance => Importance::all(),
+        all_duration => Duration::all(),
+        all_status => Status::all(),
+    })?;
+    Ok(Html(rendered).into_response())
+}
+
 // ajoute une nouvelle tâche en base
 
 pub async fn insert(
     State(state): State<Arc<AppState>>,
-    Form(input): Form<CreateTaskForm>,
-) -> Redirect {
+    input: Result<Form<CreateTaskForm>, FormRejection>,
+) -> AppResult<Redirect> {
+    let Form(input) = input?;
     let conn = state.db.lock().unwrap();
 
-    let _ = input.to_task().insert(&conn).map_err(|err| {
-        eprintln!("Erreur sql: {:?}", err);
-        StatusCode::INTERNAL_SERVER_ERROR
-    });
-    Redirect::to("/task")
+    let mut task = input.to_task()?;
+    check_parent(None, task.parent_id, &conn)?;
+    check_project(task.project_id, &conn)?;
+    task.insert(&conn)?;
+    Tag::set_for_task(task.id, &parse_tags(&input.tags), &conn)?;
+    state.publish(TaskEvent::Created { task: Task::get_by_id(task.id, &conn)? });
+    Ok(Redirect::to("/task"))
+}
+
+// affiche le formulaire d'édition d'une tâche, avec un éventuel message d'erreur
+
+fn render_edit(state: &AppState, task: &Task, note: &str, tags: &str, error: Option<&str>) -> AppResult<Html<String>> {
+    let template = state.env.get_template("task.edit")?;
+
+    let (history, parents, projects, all_tags, children, blockers, blocker_choices, score, time_entries, running) = {
+        let conn = state.db.lock().unwrap();
+        let blockers = Task::get_blockers(task.id, &conn)?;
+        let blocker_choices: Vec<Task> = Task::get_with_filter(&conn, &Filter::DailyWork)?
+            .into_iter()
+            .filter(|choice| choice.id != task.id && !blockers.iter().any(|blocker| blocker.id == choice.id))
+            .collect();
+        (
+            HistoryEntry::for_task(task.id, &conn)?,
+            parent_choices(Some(task.id), &conn)?,
+            Project::get_all(true, &conn)?,
+            Tag::get_all(&conn)?,
+            Task::get_children(task.id, &conn)?,
+            blockers,
+            blocker_choices,
+            ScoringProfile::load(&conn)?.breakdown(task, Task::waiting_count(task.id, &conn)?),
+            TimeEntry::for_task(task.id, &conn)?,
+            TimeEntry::running(&conn)?,
+        )
+    };
+
+    let rendered = template.render(context! {
+        all_priority => Priority::all(),
+        all_importance => Importance::all(),
+        all_duration => Duration::all(),
+        all_status => Status::all(),
+        task => task,
+        note => note,
+        tags => tags,
+        all_tags => all_tags,
+        history => history,
+        parents => parents,
+        projects => projects,
+        children => children,
+        blockers => blockers,
+        blocker_choices => blocker_choices,
+        score => score,
+        all_factor => Factor::all(),
+        time_spent => time_entries.iter().map(|entry| entry.seconds).sum::<i64>(),
+        time_entries => time_entries,
+        running => running,
+        all_event_kind => EventKind::all(),
+        error => error
+    })?;
+    Ok(Html(rendered))
+}
+
+// affiche le formulaire d'édition d'une tâche telle qu'enregistrée en base
+
+fn render_saved_edit(state: &AppState, id: u32, error: Option<&str>) -> AppResult<Html<String>> {
+    let (task, note, tags) = {
+        let conn = state.db.lock().unwrap();
+        (get_active(id, &conn)?, Note::get(id, &conn)?, Tag::for_task(id, &conn)?)
+    };
+    let note = note.map(|note| note.content).unwrap_or_default();
+    render_edit(state, &task, &note, &tags.join(", "), error)
 }
 
 // retourne le formulaire de mise à jour pour une tache donnée
@@ -184,82 +573,212 @@ pub async fn insert(
 pub async fn edit(
     Path(id): Path<u32>,
     State(state): State<Arc<AppState>>,
-) -> Result<Html<String>, StatusCode> {
-    let template = state.env.get_template("task.edit").unwrap();
+) -> AppResult<Html<String>> {
+    render_saved_edit(&state, id, None)
+}
 
-    let conn = state.db.lock().unwrap();
+// réaffiche la page d'édition d'une tâche enregistrée avec un message d'erreur
 
-    let task = Task::get_by_id(id, &conn).map_err(|err| {
-        eprintln!("Erreur sql: {:?}", err);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+fn edit_with_error(state: &AppState, id: u32, message: &str) -> AppError {
+    match render_saved_edit(state, id, Some(message)) {
+        Ok(html) => AppError::InvalidForm(html),
+        Err(err) => err,
+    }
+}
 
-    let rendered = template
-        .render(context! {
-            all_priority => Priority::all(),
-            all_importance => Importance::all(),
-            all_duration => Duration::all(),
-            all_status => Status::all(),
-            task => task
-        })
-        .unwrap();
-    Ok(Html(rendered))
+// ajoute une dépendance : la tâche attend la tâche choisie
+
+pub async fn add_blocker(
+    Path(id): Path<u32>,
+    State(state): State<Arc<AppState>>,
+    input: Result<Form<BlockerForm>, FormRejection>,
+) -> AppResult<Redirect> {
+    let Form(input) = input?;
+    {
+        let conn = state.db.lock().unwrap();
+        get_active(id, &conn)?;
+        if Task::can_depend_on(id, input.blocker_id, &conn)? {
+            Task::add_blocker(id, input.blocker_id, &conn)?;
+            state.publish_changed(id, &conn)?;
+            return Ok(Redirect::to(&format!("/task/{id}/edit")));
+        }
+    }
+    Err(edit_with_error(
+        &state,
+        id,
+        "Dépendance impossible : la tâche choisie n'existe pas ou dépend déjà de cette tâche",
+    ))
+}
+
+// retire une dépendance
+
+pub async fn remove_blocker(
+    Path((id, blocker_id)): Path<(u32, u32)>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Redirect> {
+    let conn = state.db.lock().unwrap();
+    get_active(id, &conn)?;
+
+    match Task::remove_blocker(id, blocker_id, &conn)? {
+        0 => Err(AppError::NotFound),
+        _ => {
+            state.publish_changed(id, &conn)?;
+            Ok(Redirect::to(&format!("/task/{id}/edit")))
+        }
+    }
 }
 
 // met à jour les données d'une tâche en base et renvoie sur index
+// si le formulaire est invalide, il est réaffiché avec l'erreur
 
 pub async fn update(
     Path(id): Path<u32>,
     State(state): State<Arc<AppState>>,
-    Form(input): Form<EditTaskForm>,
-) -> Redirect {
+    input: Result<Form<EditTaskForm>, FormRejection>,
+) -> AppResult<Redirect> {
+    let Form(input) = input?;
+    let mut task = match input.to_task() {
+        Ok(task) => task,
+        Err(AppError::BadRequest(message)) => {
+            let note = input.note.as_deref().unwrap_or_default();
+            let html = render_edit(&state, &input.draft(id), note, &input.tags, Some(&message))?;
+            return Err(AppError::InvalidForm(html));
+        }
+        Err(err) => return Err(err),
+    };
+
+    let conn = state.db.lock().unwrap();
+    let saved = get_active(id, &conn)?;
+    let invalid = match check_parent(Some(id), task.parent_id, &conn).and_then(|_| check_project(task.project_id, &conn)) {
+        Err(AppError::BadRequest(message)) => Some(message),
+        Err(err) => return Err(err),
+        Ok(()) if saved.status != Status::Finished
+            && !check_children_finished(id, task.status, &conn)?.is_empty() =>
+        {
+            Some("Impossible de terminer la tâche : des sous-tâches ne sont pas terminées".to_string())
+        }
+        Ok(()) => None,
+    };
+    if let Some(message) = invalid {
+        drop(conn);
+        let note = input.note.as_deref().unwrap_or_default();
+        let html = render_edit(&state, &input.draft(id), note, &input.tags, Some(&message))?;
+        return Err(AppError::InvalidForm(html));
+    }
+    // tâche, étiquettes et note enregistrées ensemble
+    let tx = Savepoint::new(&conn)?;
+    task.update(id, &tx)?;
+    Tag::set_for_task(id, &parse_tags(&input.tags), &tx)?;
+    // les notes sont aussi envoyées avec le formulaire au cas où l'enregistrement auto n'est pas passé
+    if let Some(note) = &input.note
+        && Note::get(id, &tx)?.is_none_or(|saved| &saved.content != note)
+    {
+        Note::save(id, note, &tx)?;
+    }
+    tx.commit()?;
+    state.publish_changed(id, &conn)?;
+    Ok(Redirect::to(&format!("/task/focus?id={id}#task{id}")))
+}
+
+// met une tâche à la corbeille et renvoie sur index
+
+pub async fn delete(Path(id): Path<u32>, State(state): State<Arc<AppState>>) -> AppResult<Redirect> {
+    let conn = state.db.lock().unwrap();
+
+    match Task::delete(id, &conn)? {
+        0 => Err(AppError::NotFound),
+        _ => {
+            state.publish(TaskEvent::Removed { id });
+            state.publish_dependents(id, &conn)?;
+            state.publish_timer(&conn)?;
+            Ok(Redirect::to("/task"))
+        }
+    }
+}
+
+// affiche la corbeille
+
+pub async fn trash(State(state): State<Arc<AppState>>) -> AppResult<Html<String>> {
+    let template = state.env.get_template("task.trash")?;
+
     let conn = state.db.lock().unwrap();
-    let _ = input.to_task().update(id, &conn).map_err(|err| {
-        eprintln!("Erreur sql: {:?}", err);
-        StatusCode::INTERNAL_SERVER_ERROR
-    });
-    Redirect::to(&format!("/task/focus?id={id}#task{id}"))
+
+    let tasks = Task::get_deleted(&conn)?;
+
+    let rendered = template.render(context! {
+        title => "Corbeille",
+        nb => tasks.len(),
+        tasks => tasks,
+        retention_days => state.retention_days,
+        all_status => Status::all(),
+    })?;
+    Ok(Html(rendered))
 }
 
-// supprime un enregistrement en base et renvoie sur index
+// sort une tâche de la corbeille et renvoie sur la corbeille
 
-pub async fn delete(Path(id): Path<u32>, State(state): State<Arc<AppState>>) -> Redirect {
+pub async fn restore(Path(id): Path<u32>, State(state): State<Arc<AppState>>) -> AppResult<Redirect> {
     let conn = state.db.lock().unwrap();
 
-    let _ = Task::delete(id, &conn).map_err(|err| {
-        eprintln!("Erreur sql: {:?}", err);
-        StatusCode::INTERNAL_SERVER_ERROR
-    });
-    Redirect::to("/task")
+    match Task::restore(id, &conn)? {
+        0 => Err(AppError::NotFound),
+        _ => {
+            state.publish(TaskEvent::Created { task: Task::get_by_id(id, &conn)? });
+            // les tâches qui l'attendent ont pu être rebloquées
+            state.publish_dependents(id, &conn)?;
+            Ok(Redirect::to("/task/trash"))
+        }
+    }
+}
+
+// supprime définitivement une tâche de la corbeille
+
+pub async fn purge(Path(id): Path<u32>, State(state): State<Arc<AppState>>) -> AppResult<Redirect> {
+    let conn = state.db.lock().unwrap();
+
+    match Task::purge(id, &conn)? {
+        0 => Err(AppError::NotFound),
+        _ => Ok(Redirect::to("/task/trash")),
+    }
 }
 
 // Met à jour le statut de la tâche
 
+// si la tâche est terminée alors que des sous-tâches sont encore ouvertes, une page
+// propose de les terminer aussi (cascade) ou d'annuler
+
 pub async fn update_status(
     Path(id): Path<u32>,
-    Query(param): Query<StatusParam>,
+    param: Result<Query<StatusParam>, QueryRejection>,
     State(state): State<Arc<AppState>>,
-) -> Redirect {
+) -> AppResult<Response> {
+    let Query(param) = param?;
     let conn = state.db.lock().unwrap();
 
-    let mut task = Task::get_by_id(id, &conn)
-        .map_err(|err| {
-            eprintln!("Erreur sql: {:?}", err);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })
-        .unwrap();
-
-    task.status = param.status;
-
-    let _ = task.update(id, &conn).map_err(|err| {
-        eprintln!("Erreur sql: {:?}", err);
-        StatusCode::INTERNAL_SERVER_ERROR
-    });
+    let task = get_active(id, &conn)?;
+    let open_children = check_children_finished(id, param.status, &conn)?;
+    let changed = if open_children.is_empty() {
+        vec![Task::update_status(id, param.status, &conn)?]
+    } else if param.cascade {
+        Task::finish_with_children(id, &conn)?
+    } else {
+        let template = state.env.get_template("task.cascade")?;
+        let rendered = template.render(context! {
+            title => "Sous-tâches en cours",
+            task => task,
+            children => open_children,
+            all_status => Status::all(),
+        })?;
+        return Ok((StatusCode::CONFLICT, Html(rendered)).into_response());
+    };
+    for task in changed {
+        state.publish_changed(task.id, &conn)?;
+    }
 
-    Redirect::to(&format!("/task/focus?id={id}#task{id}"))
+    Ok(Redirect::to(&format!("/task/focus?id={id}#task{id}")).into_response())
 }
 
-pub async fn test(State(state): State<Arc<AppState>>) -> Redirect {
+pub async fn test(State(state): State<Arc<AppState>>) -> AppResult<Redirect> {
     let conn = state.db.lock().unwrap();
     for (p, p_s) in Priority::all() {
         for (s, s_s) in Status::all() {
@@ -273,13 +792,10 @@ pub async fn test(State(state): State<Arc<AppState>>) -> Redirect {
                         importance: i,
                         ..Task::default()
                     };
-                    let _ = task.insert(&conn).map_err(|err| {
-                        eprintln!("Erreur sql: {:?}", err);
-                        StatusCode::INTERNAL_SERVER_ERROR
-                    });
+                    task.insert(&conn)?;
                 }
             }
         }
     }
-    Redirect::to("/task")
+    Ok(Redirect::to("/task"))
 }
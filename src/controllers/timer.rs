@@ -0,0 +1,101 @@
+use crate::AppState;
+use crate::error::{AppError, AppResult};
+use crate::models::task::{Status, Task};
+use crate::models::time_entry::TimeEntry;
+use crate::utils::parse_optional_date;
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{Path, Query, State};
+use axum::response::{Html, Redirect};
+use chrono::{Days, Local};
+use minijinja::context;
+use rusqlite::Connection;
+use serde::Deserialize;
+use std::sync::Arc;
+
+// Jour de la feuille de temps (aujourd'hui par défaut)
+
+#[derive(Deserialize, Debug)]
+pub struct TimesheetParam {
+    #[serde(default)]
+    day: String,
+}
+
+// démarre le chronomètre d'une tâche ouverte et diffuse les tâches concernées
+
+fn start_timer(id: u32, state: &AppState, conn: &Connection) -> AppResult<()> {
+    let task = Task::get_by_id(id, conn)?;
+    if task.deleted_at.is_some() {
+        return Err(AppError::NotFound);
+    }
+    if task.status.is_resolved() {
+        return Err(AppError::BadRequest("Le chronomètre ne peut pas être démarré sur une tâche terminée ou annulée".to_string()));
+    }
+    for changed in TimeEntry::start(id, conn)? {
+        state.publish_changed(changed, conn)?;
+    }
+    state.publish_timer(conn)?;
+    Ok(())
+}
+
+// démarre le chronomètre d'une tâche et renvoie sur la liste
+
+pub async fn start(Path(id): Path<u32>, State(state): State<Arc<AppState>>) -> AppResult<Redirect> {
+    let conn = state.db.lock().unwrap();
+    start_timer(id, &state, &conn)?;
+    Ok(Redirect::to(&format!("/task/focus?id={id}#task{id}")))
+}
+
+// arrête le chronomètre en cours et renvoie sur la tâche concernée
+
+pub async fn stop(State(state): State<Arc<AppState>>) -> AppResult<Redirect> {
+    let conn = state.db.lock().unwrap();
+    let Some(id) = TimeEntry::stop(&conn)? else {
+        return Ok(Redirect::to("/task"));
+    };
+    state.publish_changed(id, &conn)?;
+    state.publish_timer(&conn)?;
+    Ok(Redirect::to(&format!("/task/focus?id={id}#task{id}")))
+}
+
+// supprime une ligne de temps d'une tâche et renvoie sur son édition
+
+pub async fn delete_entry(
+    Path((id, entry_id)): Path<(u32, u32)>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Redirect> {
+    let conn = state.db.lock().unwrap();
+    if TimeEntry::delete(entry_id, id, &conn)? == 0 {
+        return Err(AppError::NotFound);
+    }
+    state.publish_changed(id, &conn)?;
+    state.publish_timer(&conn)?;
+    Ok(Redirect::to(&format!("/task/{id}/edit")))
+}
+
+// feuille de temps d'une journée
+
+pub async fn timesheet(
+    State(state): State<Arc<AppState>>,
+    param: Result<Query<TimesheetParam>, QueryRejection>,
+) -> AppResult<Html<String>> {
+    let Query(param) = param?;
+    let day = parse_optional_date(&param.day)
+        .map_err(|_| AppError::BadRequest(format!("Date invalide : {}", param.day)))?
+        .unwrap_or_else(|| Local::now().date_naive());
+    let template = state.env.get_template("timer.timesheet")?;
+
+    let conn = state.db.lock().unwrap();
+
+    let lines = TimeEntry::timesheet(day, &conn)?;
+    let rendered = template.render(context! {
+        title => "Feuille de temps",
+        day => day,
+        previous => day - Days::new(1),
+        next => day + Days::new(1),
+        total => lines.iter().map(|line| line.seconds).sum::<i64>(),
+        lines => lines,
+        running => TimeEntry::running(&conn)?,
+        all_status => Status::all(),
+    })?;
+    Ok(Html(rendered))
+}
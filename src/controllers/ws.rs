@@ -0,0 +1,176 @@
+use crate::AppState;
+use crate::models::note::Note;
+use crate::models::task::Task;
+use crate::models::time_entry::RunningTimer;
+use axum::extract::State;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::response::IntoResponse;
+use chrono::NaiveDateTime;
+use futures::stream::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{Duration, Instant, sleep_until};
+
+// Délai sans nouvelle saisie avant d'enregistrer les notes en base
+
+const SAVE_DELAY: Duration = Duration::from_millis(1500);
+
+// Messages envoyés par le navigateur
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    // le client indique la tâche dont il édite les notes
+    Open { task_id: u32 },
+    // nouveau contenu des notes de la tâche ouverte, numéroté par le client pour
+    // reconnaître l'envoi confirmé par Saved
+    Notes {
+        content: String,
+        #[serde(default)]
+        seq: u64,
+    },
+}
+
+// Messages envoyés au navigateur
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    // notes de l'envoi seq enregistrées en base à la date indiquée
+    Saved { task_id: u32, saved_at: NaiveDateTime, seq: u64 },
+    Error { message: String },
+}
+
+// Evénements diffusés à tous les clients quand une tâche est créée, modifiée ou supprimée,
+// quand le score de tâches a été recalculé, ou quand le chronomètre démarre ou s'arrête
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum TaskEvent {
+    #[serde(rename = "task_created")]
+    Created { task: Task },
+    #[serde(rename = "task_changed")]
+    Changed { task: Task },
+    #[serde(rename = "task_removed")]
+    Removed { id: u32 },
+    // scores recalculés en masse : les pages rechargent leur liste
+    #[serde(rename = "scores_refreshed")]
+    ScoresRefreshed { count: usize },
+    #[serde(rename = "timer_changed")]
+    TimerChanged { running: Option<RunningTimer> },
+}
+
+// Endpoint qui upgrade en WebSocket
+
+pub async fn handle_ws(ws: WebSocketUpgrade, State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_socket(socket, state))
+}
+
+// Etat d'une connexion : tâche ouverte et notes reçues mais pas encore enregistrées
+
+#[derive(Default)]
+struct Session {
+    task_id: Option<u32>,
+    pending: Option<(String, u64)>,
+    deadline: Option<Instant>,
+}
+
+// Gère la connexion WebSocket une fois établie
+
+async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
+    let mut session = Session::default();
+    let mut events = state.events.subscribe();
+    loop {
+        let deadline = session.deadline;
+        tokio::select! {
+            msg = socket.next() => {
+                let Some(Ok(msg)) = msg else { break };
+                let reply = match msg {
+                    Message::Text(text) => on_message(&text, &mut session, &state),
+                    Message::Close(_) => break,
+                    _ => None,
+                };
+                if let Some(reply) = reply && send(&mut socket, &reply).await.is_err() {
+                    break;
+                }
+            }
+            event = events.recv() => {
+                match event {
+                    Ok(event) => if send(&mut socket, &event).await.is_err() {
+                        break;
+                    },
+                    // client trop lent : les événements perdus sont ignorés
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+            }
+            _ = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                let reply = flush(&mut session, &state);
+                if let Some(reply) = reply && send(&mut socket, &reply).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    // on n'abandonne pas une saisie en attente si le navigateur se ferme
+    flush(&mut session, &state);
+}
+
+fn on_message(text: &str, session: &mut Session, state: &AppState) -> Option<ServerMessage> {
+    let message = match serde_json::from_str::<ClientMessage>(text) {
+        Ok(message) => message,
+        Err(err) => return Some(ServerMessage::Error { message: format!("Message invalide : {err}") }),
+    };
+    match message {
+        ClientMessage::Open { task_id } => {
+            // changement de tâche : les notes de la précédente sont enregistrées d'abord
+            let flushed = flush(session, state);
+            let conn = state.db.lock().unwrap();
+            // les notes d'une tâche à la corbeille ne sont plus modifiables
+            match Task::get_by_id(task_id, &conn) {
+                Ok(task) if task.deleted_at.is_none() => {
+                    session.task_id = Some(task_id);
+                    flushed
+                }
+                _ => {
+                    session.task_id = None;
+                    Some(ServerMessage::Error { message: format!("Tâche {task_id} introuvable") })
+                }
+            }
+        }
+        ClientMessage::Notes { content, seq } => {
+            if session.task_id.is_none() {
+                return Some(ServerMessage::Error { message: "Aucune tâche ouverte".to_string() });
+            }
+            session.pending = Some((content, seq));
+            session.deadline = Some(Instant::now() + SAVE_DELAY);
+            None
+        }
+    }
+}
+
+// Enregistre les notes en attente et retourne l'accusé de réception à envoyer au client
+
+fn flush(session: &mut Session, state: &AppState) -> Option<ServerMessage> {
+    session.deadline = None;
+    let (task_id, (content, seq)) = (session.task_id?, session.pending.take()?);
+    let conn = state.db.lock().unwrap();
+    // la tâche a pu partir à la corbeille entre-temps : la saisie en attente est abandonnée
+    if !matches!(Task::get_by_id(task_id, &conn), Ok(task) if task.deleted_at.is_none()) {
+        session.task_id = None;
+        return Some(ServerMessage::Error { message: format!("Tâche {task_id} introuvable") });
+    }
+    match Note::save(task_id, &content, &conn) {
+        Ok(note) => Some(ServerMessage::Saved { task_id, saved_at: note.updated_at, seq }),
+        Err(err) => {
+            eprintln!("Erreur sql: {:?}", err);
+            Some(ServerMessage::Error { message: "Erreur lors de l'enregistrement des notes".to_string() })
+        }
+    }
+}
+
+async fn send(socket: &mut WebSocket, message: &impl Serialize) -> Result<(), axum::Error> {
+    let json = serde_json::to_string(message).unwrap_or_default();
+    socket.send(Message::Text(json.into())).await
+}
@@ -0,0 +1,102 @@
+use axum::extract::rejection::{FormRejection, QueryRejection};
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Response};
+use minijinja::{Environment, context};
+
+use crate::views;
+
+// Erreur applicative des contrôleurs html : chaque variante est convertie en page d'erreur
+// avec le code http correspondant au lieu de faire paniquer le worker
+
+#[derive(Debug)]
+pub enum AppError {
+    // l'enregistrement demandé n'existe pas (404)
+    NotFound,
+    // la requête ou le formulaire est invalide (400)
+    BadRequest(String),
+    // le formulaire est invalide et a déjà été réaffiché avec l'erreur (400)
+    InvalidForm(Html<String>),
+    // erreur de la base : contrainte, entrée/sortie... (500)
+    Database(rusqlite::Error),
+    // erreur de chargement ou de rendu d'un template (500)
+    Template(minijinja::Error),
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl From<rusqlite::Error> for AppError {
+    fn from(err: rusqlite::Error) -> Self {
+        match err {
+            rusqlite::Error::QueryReturnedNoRows => AppError::NotFound,
+            err => AppError::Database(err),
+        }
+    }
+}
+
+impl From<minijinja::Error> for AppError {
+    fn from(err: minijinja::Error) -> Self {
+        AppError::Template(err)
+    }
+}
+
+impl From<FormRejection> for AppError {
+    fn from(rejection: FormRejection) -> Self {
+        AppError::BadRequest(rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for AppError {
+    fn from(rejection: QueryRejection) -> Self {
+        AppError::BadRequest(rejection.body_text())
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (code, title, message) = match self {
+            AppError::InvalidForm(html) => return (StatusCode::BAD_REQUEST, html).into_response(),
+            AppError::NotFound => (
+                StatusCode::NOT_FOUND,
+                "Introuvable",
+                "L'élément demandé n'existe pas ou a été supprimé.".to_string(),
+            ),
+            AppError::BadRequest(message) => (StatusCode::BAD_REQUEST, "Requête invalide", message),
+            AppError::Database(err) => {
+                eprintln!("Erreur sql: {:?}", err);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Erreur interne",
+                    "Erreur d'accès à la base de données, les modifications n'ont pas été enregistrées.".to_string(),
+                )
+            }
+            AppError::Template(err) => {
+                eprintln!("Erreur template: {:?}", err);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Erreur interne",
+                    "Erreur lors de l'affichage de la page.".to_string(),
+                )
+            }
+        };
+        (code, render_error_page(code, title, &message)).into_response()
+    }
+}
+
+// La page d'erreur est rendue sans l'état de l'application : on recharge les templates
+// (inclus dans le binaire) et on se rabat sur du texte brut si le rendu échoue
+
+fn render_error_page(code: StatusCode, title: &str, message: &str) -> Html<String> {
+    let mut env = Environment::new();
+    views::template::add_template(&mut env);
+    let rendered = env.get_template("error").and_then(|template| {
+        template.render(context! {
+            title => title,
+            code => code.as_u16(),
+            message => message,
+        })
+    });
+    match rendered {
+        Ok(html) => Html(html),
+        Err(_) => Html(format!("<h1>{} - {title}</h1><p>{message}</p>", code.as_u16())),
+    }
+}
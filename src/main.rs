@@ -1,14 +1,21 @@
+mod cli;
+mod config;
 mod controllers;
+mod error;
 mod models;
 mod utils;
 mod views;
 
 use std::sync::{Arc, Mutex};
-use axum::{Router, http::header, response::IntoResponse, routing::get};
-use axum::extract::ws::{Message, WebSocket,WebSocketUpgrade};
+use axum::{Router, http::header, response::IntoResponse, routing::{delete, get, post, put}};
 use minijinja::Environment;
 use rusqlite::Connection;
-use futures::stream::StreamExt;
+use tokio::sync::broadcast;
+
+use controllers::ws::TaskEvent;
+use models::backup::Restored;
+use models::task::{Status, Task};
+use models::time_entry::TimeEntry;
 
 const BOOTSTRAP_CSS: &[u8] = include_bytes!("./static/css/bootstrap.min.css");
 const BOOTSTRAP_JS: &[u8] = include_bytes!("./static/js/bootstrap.bundle.min.js");
@@ -16,6 +23,72 @@ const BOOTSTRAP_JS: &[u8] = include_bytes!("./static/js/bootstrap.bundle.min.js"
 struct AppState {
     env: Environment<'static>,
     db: Arc<Mutex<Connection>>,
+    events: broadcast::Sender<TaskEvent>,
+    retention_days: u32,
+}
+
+impl AppState {
+    // diffuse un événement sur les tâches à tous les clients connectés au WebSocket
+    fn publish(&self, event: TaskEvent) {
+        // une erreur signifie simplement qu'aucun client n'est connecté
+        let _ = self.events.send(event);
+    }
+
+    // diffuse la modification d'une tâche et des tâches qui en dépendent,
+    // qui ont pu être débloquées automatiquement
+    fn publish_changed(&self, id: u32, conn: &Connection) -> rusqlite::Result<()> {
+        let task = Task::get_by_id(id, conn)?;
+        // une tâche récurrente terminée a pu créer son occurrence suivante
+        if task.status == Status::Finished
+            && let Some(next) = Task::get_next_occurrence(id, conn)?
+        {
+            self.publish(TaskEvent::Created { task: next });
+        }
+        // une tâche terminée ou annulée a pu arrêter le chronomètre
+        let resolved = task.status.is_resolved();
+        self.publish(TaskEvent::Changed { task });
+        if resolved {
+            self.publish_timer(conn)?;
+        }
+        self.publish_dependents(id, conn)
+    }
+
+    // diffuse l'état du chronomètre
+    fn publish_timer(&self, conn: &Connection) -> rusqlite::Result<()> {
+        self.publish(TaskEvent::TimerChanged { running: TimeEntry::running(conn)? });
+        Ok(())
+    }
+
+    // recalcule le score de toutes les tâches ; un seul événement est diffusé pour toutes
+    // celles qui ont changé, plutôt qu'une mise à jour par tâche
+    fn rescore_all(&self, conn: &Connection) -> rusqlite::Result<usize> {
+        let count = Task::recompute_scores(conn)?.len();
+        if count > 0 {
+            self.publish(TaskEvent::ScoresRefreshed { count });
+        }
+        Ok(count)
+    }
+
+    // diffuse le résultat d'une restauration : tâches retirées puis tâches restaurées
+    fn publish_restored(&self, restored: &Restored, conn: &Connection) -> rusqlite::Result<()> {
+        for id in &restored.removed {
+            self.publish(TaskEvent::Removed { id: *id });
+        }
+        for id in &restored.tasks {
+            let task = Task::get_by_id(*id, conn)?;
+            if task.deleted_at.is_none() {
+                self.publish(TaskEvent::Created { task });
+            }
+        }
+        self.publish_timer(conn)
+    }
+
+    fn publish_dependents(&self, id: u32, conn: &Connection) -> rusqlite::Result<()> {
+        for task in Task::get_dependents(id, conn)? {
+            self.publish(TaskEvent::Changed { task });
+        }
+        Ok(())
+    }
 }
 
 async fn serve_bootstrap_css() -> impl IntoResponse {
@@ -30,62 +103,174 @@ async fn serve_bootstrap_js() -> impl IntoResponse {
     )
 }
 
-// Endpoint qui upgrade en WebSocket
-async fn handle_ws(ws: WebSocketUpgrade) -> impl IntoResponse {
-    ws.on_upgrade(handle_socket)
+// Vide régulièrement la corbeille des tâches supprimées depuis plus de retention_days jours
+
+async fn purge_trash(state: Arc<AppState>) {
+    if state.retention_days == 0 {
+        return;
+    }
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+    loop {
+        interval.tick().await;
+        let limit = chrono::Local::now().naive_local() - chrono::Duration::days(state.retention_days.into());
+        let conn = state.db.lock().unwrap();
+        match Task::purge_deleted_before(limit, &conn) {
+            Ok(0) => {}
+            Ok(nb) => println!("Corbeille : {nb} tâche(s) supprimée(s) définitivement"),
+            Err(err) => eprintln!("Erreur sql: {:?}", err),
+        }
+    }
 }
 
-// Gère la connexion WebSocket une fois établie
-async fn handle_socket(mut socket: WebSocket) {
-    while let Some(Ok(msg)) = socket.next().await {
-        match msg {
-            Message::Text(text) => {
-                println!("Reçu: {}", text);
-                let _ = socket.send(Message::Text(format!("Echo: {}", text).into())).await;
-            }
-            Message::Close(_) => break,
-            _ => {}
+// Recalcule régulièrement le score des tâches, qui évolue avec l'échéance et l'ancienneté
+
+async fn refresh_scores(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+    loop {
+        interval.tick().await;
+        let conn = state.db.lock().unwrap();
+        if let Err(err) = state.rescore_all(&conn) {
+            eprintln!("Erreur sql: {:?}", err);
         }
     }
 }
 
+// Ouvre la base (créée au besoin avec son répertoire) et la met à jour
+
+fn open_database(config: &config::Config) -> rusqlite::Result<Connection> {
+    if let Some(dir) = config.db_path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|_| rusqlite::Error::InvalidPath(dir.to_path_buf()))?;
+    }
+    let mut conn = Connection::open(&config.db_path)?;
+    // les clés étrangères (REFERENCES, ON DELETE CASCADE) ne sont appliquées par sqlite
+    // que si elles sont activées sur la connexion : on ne dépend pas des options de compilation
+    conn.pragma_update(None, "foreign_keys", true)?;
+    models::migration::run(&mut conn)?;
+    Ok(conn)
+}
+
 #[tokio::main]
 async fn main() {
-    let port;
-    match utils::get_port_from_args() {
-        Ok(p) => port = p,
+    let args: Vec<String> = std::env::args().collect();
+    let parsed = cli::Command::from_args(&args).and_then(|command| Ok((command, config::Config::from_env()?)));
+    let (command, config) = match parsed {
+        Ok((cli::Command::Help, _)) => {
+            utils::print_usage();
+            return;
+        }
+        Ok(parsed) => parsed,
         Err(err) => {
             eprintln!("{}", err);
             utils::print_usage();
             std::process::exit(1);
         }
+    };
+    if !matches!(command, cli::Command::Serve) {
+        if let Err(err) = cli::run(command, &config) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
     }
 
-    let conn = Arc::new(Mutex::new(
-        Connection::open(".\\todo.db").expect("❌ Erreur de connexion"),
-    ));
+    let port = config.port;
+    let conn = match open_database(&config) {
+        Ok(conn) => Arc::new(Mutex::new(conn)),
+        Err(err) => {
+            eprintln!("❌ Erreur d'ouverture de la base {} : {err}", config.db_path.display());
+            std::process::exit(1);
+        }
+    };
 
+    println!("Database : {}", config.db_path.display());
     println!("Server starts on port : {port}");
     println!("Type http://localhost:{port} in your browser.");
 
     let mut env = Environment::new();
     views::template::add_template(&mut env);
     env.add_filter("format_date", utils::format_date);
+    env.add_filter("format_datetime", utils::format_datetime);
+    env.add_filter("format_seconds", utils::format_seconds);
+    env.add_filter("highlight", utils::highlight);
+    env.add_filter("overdue", utils::overdue);
+    env.add_filter("recurrence_label", utils::recurrence_label);
 
-    let app_state = Arc::new(AppState { env, db: conn });
+    let (events, _) = broadcast::channel(100);
+    let app_state = Arc::new(AppState { env, db: conn, events, retention_days: config.retention_days });
+    tokio::spawn(purge_trash(app_state.clone()));
+    tokio::spawn(refresh_scores(app_state.clone()));
     let app = Router::new()
         .route("/", get(controllers::home::controller_home))
         .route("/task", get(controllers::task::index))
         .route("/task/focus", get(controllers::task::focus))
         .route("/task/filter", get(controllers::task::filter))
+        .route("/task/search", get(controllers::task::search))
         .route("/task/create", get(controllers::task::create).post(controllers::task::insert),)
         .route("/task/{id}/edit", get(controllers::task::edit).post(controllers::task::update),)
-        .route("/task/{id}/delete", get(controllers::task::delete),)
+        .route("/task/{id}/row", get(controllers::task::row),)
+        .route("/task/{id}/delete", post(controllers::task::delete),)
+        .route("/task/{id}/restore", post(controllers::task::restore),)
+        .route("/task/{id}/blockers", post(controllers::task::add_blocker),)
+        .route("/task/{id}/blockers/{blocker_id}/delete", post(controllers::task::remove_blocker),)
+        .route("/task/{id}/purge", post(controllers::task::purge),)
+        .route("/task/trash", get(controllers::task::trash))
+        .route("/task/export", get(controllers::csv_transfer::export))
+        .route("/task/import", get(controllers::csv_transfer::import_form).post(controllers::csv_transfer::import))
+        .route("/task/import/preview", post(controllers::csv_transfer::preview))
         .route("/task/{id}/update_status", get(controllers::task::update_status),)
+        .route("/task/{id}/timer/start", post(controllers::timer::start))
+        .route("/task/{id}/time/{entry_id}/delete", post(controllers::timer::delete_entry))
+        .route("/timer/stop", post(controllers::timer::stop))
+        .route("/timesheet", get(controllers::timer::timesheet))
+        .route("/project", get(controllers::project::index))
+        .route("/project/create", get(controllers::project::create).post(controllers::project::insert),)
+        .route("/project/{id}", get(controllers::project::show))
+        .route("/project/{id}/edit", get(controllers::project::edit).post(controllers::project::update),)
+        .route("/dashboard/matrix", get(controllers::dashboard::matrix))
+        .route("/dashboard/matrix/{id}/card", get(controllers::dashboard::card))
+        .route("/dashboard/matrix/{id}/move", post(controllers::dashboard::move_task))
+        .route("/dashboard/kanban", get(controllers::dashboard::kanban))
+        .route("/dashboard/kanban/{id}/card", get(controllers::dashboard::kanban_card))
+        .route("/task/export.txt", get(controllers::plain_text::export_todo_txt))
+        .route("/task/export.md", get(controllers::plain_text::export_markdown))
+        .route("/todo_txt/import", get(controllers::plain_text::import_form).post(controllers::plain_text::import))
+        .route("/calendar.ics", get(controllers::calendar::feed))
+        .route("/calendar/import", get(controllers::calendar::import_form).post(controllers::calendar::import))
+        .route("/backup", get(controllers::backup::index))
+        .route("/backup/export", get(controllers::backup::export))
+        .route("/backup/restore", post(controllers::backup::restore))
+        .route("/stats", get(controllers::stats::index))
+        .route("/settings/scoring", get(controllers::settings::scoring).post(controllers::settings::save_scoring))
+        .route("/settings/scoring/reset", post(controllers::settings::reset_scoring))
+        .route("/api/v1/tasks", get(controllers::api::list).post(controllers::api::create))
+        .route("/api/v1/tasks/import", post(controllers::api::import_csv))
+        .route("/api/v1/tasks/{id}", get(controllers::api::get).patch(controllers::api::patch).delete(controllers::api::delete))
+        .route("/api/v1/tasks/{id}/status", put(controllers::api::update_status))
+        .route("/api/v1/tasks/{id}/events", get(controllers::api::task_events))
+        .route("/api/v1/tasks/{id}/restore", post(controllers::api::restore))
+        .route("/api/v1/tasks/{id}/blockers", get(controllers::api::blockers).post(controllers::api::add_blocker))
+        .route("/api/v1/tasks/{id}/blockers/{blocker_id}", delete(controllers::api::remove_blocker))
+        .route("/api/v1/tasks/{id}/tags", get(controllers::api::tags).put(controllers::api::set_tags))
+        .route("/api/v1/tasks/{id}/score", get(controllers::api::score))
+        .route("/api/v1/scoring", get(controllers::api::scoring).put(controllers::api::set_scoring))
+        .route("/api/v1/scoring/recompute", post(controllers::api::recompute_scores))
+        .route("/api/v1/stats", get(controllers::api::stats))
+        .route("/api/v1/tasks/{id}/timer/start", post(controllers::api::start_timer))
+        .route("/api/v1/tasks/{id}/time_entries", get(controllers::api::time_entries))
+        .route("/api/v1/timer", get(controllers::api::timer))
+        .route("/api/v1/timer/stop", post(controllers::api::stop_timer))
+        .route("/api/v1/timesheet", get(controllers::api::timesheet))
+        .route("/api/v1/todo_txt/import", post(controllers::api::import_todo_txt))
+        .route("/api/v1/calendar/import", post(controllers::api::import_calendar))
+        .route("/api/v1/backup", get(controllers::api::backup))
+        .route("/api/v1/backup/restore", post(controllers::api::restore_backup))
+        .route("/api/v1/events", get(controllers::api::events))
+        .route("/api/v1/projects", get(controllers::api::projects))
+        .route("/api/v1/tags", get(controllers::api::all_tags))
         .route("/test", get(controllers::task::test),)
         .route("/css/bootstrap.min.css", get(serve_bootstrap_css))
         .route("/js/bootstrap.bundle.min.js", get(serve_bootstrap_js))
-        .route("/ws", get(handle_ws))
+        .route("/ws", get(controllers::ws::handle_ws))
         .with_state(app_state);
     let listener = tokio::net::TcpListener::bind(format!("127.0.0.1:{port}"))
         .await
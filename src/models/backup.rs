@@ -0,0 +1,339 @@
+use crate::models::history::HistoryEntry;
+use crate::models::migration;
+use crate::models::note::Note;
+use crate::models::project::Project;
+use crate::models::savepoint::Savepoint;
+use crate::models::scoring::ScoringProfile;
+use crate::models::tag::Tag;
+use crate::models::task::{Filter, Task};
+use crate::models::time_entry::TimeEntry;
+
+use chrono::{Local, NaiveDateTime};
+use rusqlite::{Connection, OptionalExtension, Result, params};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// Sauvegarde complète de la base dans un document JSON versionné : tâches (corbeille
+// comprise), projets, étiquettes, dépendances, notes, temps passé, historique et poids
+// du score. La restauration remplace la base ou y ajoute les tâches du document, dans
+// une seule transaction
+
+// Identifiant du format et version du document produits par cette version de l'application
+
+pub const FORMAT: &str = "todo-backup";
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backup {
+    pub format: String,
+    pub version: u32,
+    // version du schéma de la base exportée (pour information)
+    pub schema_version: usize,
+    pub exported_at: NaiveDateTime,
+    pub projects: Vec<Project>,
+    pub tasks: Vec<Task>,
+    // noms des étiquettes de chaque tâche
+    pub tags: BTreeMap<u32, Vec<String>>,
+    pub dependencies: Vec<Dependency>,
+    pub notes: Vec<Note>,
+    pub time_entries: Vec<TimeEntry>,
+    pub events: Vec<HistoryEntry>,
+    pub scoring: ScoringProfile,
+}
+
+// task_id attend blocker_id
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dependency {
+    pub task_id: u32,
+    pub blocker_id: u32,
+}
+
+// Restauration : remplacer tout le contenu de la base, ou ajouter les tâches du document
+// (avec de nouveaux identifiants) en rattachant les projets existants par leur nom
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreMode {
+    Replace,
+    Merge,
+}
+
+// Résultat d'une restauration : identifiants des tâches retirées et des tâches restaurées
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Restored {
+    pub removed: Vec<u32>,
+    pub tasks: Vec<u32>,
+    pub projects: usize,
+}
+
+// insère une tâche telle quelle (sans recalcul du score ni historique), avec son
+// identifiant ou un nouveau si id est None
+fn insert_task(id: Option<u32>, task: &Task, conn: &Connection) -> Result<u32> {
+    conn.execute(
+        "INSERT INTO tasks (id, description, priority, importance, duration, creation_date, completion_date, start_date, status, project_id, scoring, deleted_at, parent_id, due_date, due_time, recurrence, previous_id)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17);",
+        params![
+            id, task.description, task.priority, task.importance, task.duration, task.creation_date,
+            task.completion_date, task.start_date, task.status, task.project_id, task.scoring, task.deleted_at,
+            task.parent_id, task.due_date, task.due_time, task.recurrence, task.previous_id,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid() as u32)
+}
+
+fn insert_tag(task_id: u32, name: &str, conn: &Connection) -> Result<()> {
+    conn.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1);", params![name])?;
+    conn.execute(
+        "INSERT OR IGNORE INTO task_tags (task_id, tag_id) SELECT ?1, id FROM tags WHERE name = ?2;",
+        params![task_id, name],
+    )?;
+    Ok(())
+}
+
+impl Backup {
+
+    // document de sauvegarde de toute la base
+    pub fn export(conn: &Connection) -> Result<Backup> {
+        let mut tasks = Task::get_with_filter(conn, &Filter::All)?;
+        tasks.extend(Task::get_deleted(conn)?);
+        tasks.sort_by_key(|task| task.id);
+        let mut stmt = conn.prepare("SELECT task_id, blocker_id FROM task_dependencies ORDER BY task_id, blocker_id")?;
+        let dependencies = stmt
+            .query_map([], |row| Ok(Dependency { task_id: row.get(0)?, blocker_id: row.get(1)? }))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Backup {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            schema_version: migration::version(conn)?,
+            exported_at: Local::now().naive_local(),
+            projects: Project::get_all(true, conn)?,
+            tasks,
+            tags: Tag::by_task(conn)?.into_iter().collect(),
+            dependencies,
+            notes: Note::get_all(conn)?,
+            time_entries: TimeEntry::get_all(conn)?,
+            events: HistoryEntry::between(conn, None, None, None)?,
+            scoring: ScoringProfile::load(conn)?,
+        })
+    }
+
+    // vérifie que le document est une sauvegarde lisible et cohérente : format, version,
+    // identifiants uniques et références vers des tâches et projets du document
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        if self.format != FORMAT {
+            errors.push(format!("Format inconnu « {} », une sauvegarde « {FORMAT} » est attendue", self.format));
+        }
+        if self.version == 0 || self.version > VERSION {
+            errors.push(format!("Version {} du document non prise en charge (version {VERSION} au plus)", self.version));
+        }
+
+        let mut project_ids = HashSet::new();
+        let mut project_names = HashSet::new();
+        for project in &self.projects {
+            if !project_ids.insert(project.id) {
+                errors.push(format!("Projet {} en double", project.id));
+            }
+            if project.name.trim().is_empty() || !project_names.insert(project.name.clone()) {
+                errors.push(format!("Nom de projet vide ou en double : « {} »", project.name));
+            }
+        }
+        let mut task_ids = HashSet::new();
+        for task in &self.tasks {
+            if !task_ids.insert(task.id) {
+                errors.push(format!("Tâche {} en double", task.id));
+            }
+        }
+        let unknown_task = |id: &u32| !task_ids.contains(id);
+        for task in &self.tasks {
+            if task.project_id.is_some_and(|id| !project_ids.contains(&id)) {
+                errors.push(format!("Tâche {} : projet {} inconnu", task.id, task.project_id.unwrap_or_default()));
+            }
+            for (label, link) in [("tâche parente", task.parent_id), ("occurrence précédente", task.previous_id)] {
+                if let Some(id) = link.filter(unknown_task) {
+                    errors.push(format!("Tâche {} : {label} {id} inconnue", task.id));
+                }
+            }
+        }
+        for task_id in self.tags.keys().filter(|id| unknown_task(id)) {
+            errors.push(format!("Étiquettes d'une tâche inconnue : {task_id}"));
+        }
+        for dependency in &self.dependencies {
+            if unknown_task(&dependency.task_id) || unknown_task(&dependency.blocker_id) {
+                errors.push(format!("Dépendance entre tâches inconnues : {} → {}", dependency.task_id, dependency.blocker_id));
+            }
+        }
+        for note in self.notes.iter().filter(|note| unknown_task(&note.task_id)) {
+            errors.push(format!("Note d'une tâche inconnue : {}", note.task_id));
+        }
+        for entry in self.time_entries.iter().filter(|entry| unknown_task(&entry.task_id)) {
+            errors.push(format!("Temps passé sur une tâche inconnue : {}", entry.task_id));
+        }
+        if self.time_entries.iter().filter(|entry| entry.stopped_at.is_none()).count() > 1 {
+            errors.push("Plusieurs chronomètres sont en cours".to_string());
+        }
+        errors
+    }
+
+    // restaure le document dans la base (à valider avant), tout ou rien
+    pub fn restore(&self, mode: RestoreMode, conn: &Connection) -> Result<Restored> {
+        let tx = Savepoint::new(conn)?;
+        // les clés étrangères (activées à l'ouverture de la base) ne sont vérifiées qu'à la
+        // fin : une tâche peut avoir pour parente une tâche insérée après elle
+        tx.pragma_update(None, "defer_foreign_keys", true)?;
+        let restored = match mode {
+            RestoreMode::Replace => self.replace(&tx)?,
+            RestoreMode::Merge => self.merge(&tx)?,
+        };
+        tx.commit()?;
+        Ok(restored)
+    }
+
+    // vide la base et la remplit avec le document, identifiants compris
+    fn replace(&self, conn: &Connection) -> Result<Restored> {
+        let mut stmt = conn.prepare("SELECT id FROM tasks ORDER BY id")?;
+        let removed = stmt.query_map([], |row| row.get(0))?.collect::<Result<Vec<u32>, _>>()?;
+        // les triggers de suppression des tâches vident aussi l'index, les notes, les étiquettes et le temps passé
+        conn.execute_batch(
+            "DELETE FROM task_dependencies; DELETE FROM tasks; DELETE FROM tags; DELETE FROM projects;
+            DELETE FROM task_events; DELETE FROM scoring_weights;",
+        )?;
+
+        for project in &self.projects {
+            conn.execute(
+                "INSERT INTO projects (id, name, color, description, archived) VALUES (?1, ?2, ?3, ?4, ?5);",
+                params![project.id, project.name, project.color, project.description, project.archived],
+            )?;
+        }
+        for task in &self.tasks {
+            insert_task(Some(task.id), task, conn)?;
+        }
+        for (task_id, names) in &self.tags {
+            for name in names {
+                insert_tag(*task_id, name, conn)?;
+            }
+        }
+        for dependency in &self.dependencies {
+            conn.execute(
+                "INSERT OR IGNORE INTO task_dependencies (task_id, blocker_id) VALUES (?1, ?2);",
+                params![dependency.task_id, dependency.blocker_id],
+            )?;
+        }
+        for note in &self.notes {
+            conn.execute(
+                "INSERT INTO notes (task_id, content, updated_at) VALUES (?1, ?2, ?3);",
+                params![note.task_id, note.content, note.updated_at],
+            )?;
+        }
+        for entry in &self.time_entries {
+            conn.execute(
+                "INSERT INTO time_entries (id, task_id, started_at, stopped_at) VALUES (?1, ?2, ?3, ?4);",
+                params![entry.id, entry.task_id, entry.started_at, entry.stopped_at],
+            )?;
+        }
+        for event in &self.events {
+            let (old_status, new_status) = event.statuses();
+            conn.execute(
+                "INSERT INTO task_events (id, task_id, kind, field, old_value, new_value, old_status, new_status, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9);",
+                params![event.id, event.task_id, event.kind, event.field, event.old_value, event.new_value, old_status, new_status, event.created_at],
+            )?;
+        }
+        self.scoring.save(conn)?;
+
+        Ok(Restored {
+            removed,
+            tasks: self.tasks.iter().map(|task| task.id).collect(),
+            projects: self.projects.len(),
+        })
+    }
+
+    // ajoute les tâches du document avec de nouveaux identifiants ; les liens entre tâches
+    // suivent la renumérotation. Les poids du score de la base sont conservés et un
+    // chronomètre en cours dans le document est arrêté à la date de l'export
+    fn merge(&self, conn: &Connection) -> Result<Restored> {
+        let mut project_map: HashMap<u32, u32> = HashMap::new();
+        let mut created_projects = 0;
+        for project in &self.projects {
+            let existing: Option<u32> = conn
+                .query_row("SELECT id FROM projects WHERE name = ?1", params![project.name], |row| row.get(0))
+                .optional()?;
+            let id = match existing {
+                Some(id) => id,
+                None => {
+                    let mut project = Project { id: 0, ..project.clone() };
+                    project.insert(conn)?;
+                    created_projects += 1;
+                    project.id
+                }
+            };
+            project_map.insert(project.id, id);
+        }
+
+        let mut task_map: HashMap<u32, u32> = HashMap::new();
+        for task in &self.tasks {
+            let task = Task {
+                project_id: task.project_id.and_then(|id| project_map.get(&id).copied()),
+                parent_id: None,
+                previous_id: None,
+                ..task.clone()
+            };
+            task_map.insert(task.id, insert_task(None, &task, conn)?);
+        }
+        let mapped = |id: &u32| task_map.get(id).copied();
+        for task in &self.tasks {
+            if task.parent_id.is_some() || task.previous_id.is_some() {
+                conn.execute(
+                    "UPDATE tasks SET parent_id = ?1, previous_id = ?2 WHERE id = ?3;",
+                    params![task.parent_id.and_then(|id| mapped(&id)), task.previous_id.and_then(|id| mapped(&id)), mapped(&task.id)],
+                )?;
+            }
+        }
+
+        for (task_id, names) in &self.tags {
+            if let Some(task_id) = mapped(task_id) {
+                for name in names {
+                    insert_tag(task_id, name, conn)?;
+                }
+            }
+        }
+        for dependency in &self.dependencies {
+            if let (Some(task_id), Some(blocker_id)) = (mapped(&dependency.task_id), mapped(&dependency.blocker_id)) {
+                conn.execute(
+                    "INSERT OR IGNORE INTO task_dependencies (task_id, blocker_id) VALUES (?1, ?2);",
+                    params![task_id, blocker_id],
+                )?;
+            }
+        }
+        for note in &self.notes {
+            if let Some(task_id) = mapped(&note.task_id) {
+                conn.execute(
+                    "INSERT INTO notes (task_id, content, updated_at) VALUES (?1, ?2, ?3);",
+                    params![task_id, note.content, note.updated_at],
+                )?;
+            }
+        }
+        for entry in &self.time_entries {
+            if let Some(task_id) = mapped(&entry.task_id) {
+                conn.execute(
+                    "INSERT INTO time_entries (task_id, started_at, stopped_at) VALUES (?1, ?2, ?3);",
+                    params![task_id, entry.started_at, entry.stopped_at.unwrap_or(self.exported_at.max(entry.started_at))],
+                )?;
+            }
+        }
+        for event in &self.events {
+            if let Some(task_id) = mapped(&event.task_id) {
+                let (old_status, new_status) = event.statuses();
+                HistoryEntry { old_status, new_status, ..event.clone() }.insert(task_id, conn)?;
+            }
+        }
+
+        Ok(Restored {
+            removed: vec![],
+            tasks: self.tasks.iter().filter_map(|task| mapped(&task.id)).collect(),
+            projects: created_projects,
+        })
+    }
+
+}
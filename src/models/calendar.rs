@@ -0,0 +1,474 @@
+use crate::models::csv_transfer::{self, ImportRow};
+use crate::models::note::Note;
+use crate::models::recurrence::Recurrence;
+use crate::models::savepoint::Savepoint;
+use crate::models::tag::parse_tags;
+use crate::models::task::{Priority, Status, Task};
+
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use rusqlite::{Connection, Result};
+use std::collections::HashMap;
+
+// Tâches au format iCalendar (RFC 5545) : chaque tâche est un composant VTODO.
+// Le projet est écrit dans X-TODO-PROJECT et les étiquettes dans CATEGORIES pour
+// être relus à l'import
+
+const PRODID: &str = "-//todo//Todo liste//FR";
+// longueur maximale d'une ligne, en octets, avant repli
+const LINE_LENGTH: usize = 75;
+
+// Statut iCalendar d'une tâche (une tâche bloquée reste à faire)
+fn ics_status(status: Status) -> &'static str {
+    match status {
+        Status::ToDo | Status::Blocked => "NEEDS-ACTION",
+        Status::InProgress => "IN-PROCESS",
+        Status::Finished => "COMPLETED",
+        Status::Canceled => "CANCELLED",
+    }
+}
+
+fn parse_status(value: &str) -> Option<Status> {
+    match value.to_uppercase().as_str() {
+        "NEEDS-ACTION" => Some(Status::ToDo),
+        "IN-PROCESS" => Some(Status::InProgress),
+        "COMPLETED" => Some(Status::Finished),
+        "CANCELLED" => Some(Status::Canceled),
+        _ => None,
+    }
+}
+
+// Priorité iCalendar : 1 à 4 haute, 5 moyenne, 6 à 9 basse, 0 non définie
+fn ics_priority(priority: Priority) -> u8 {
+    match priority {
+        Priority::ToBeDefined => 0,
+        Priority::VeryUrgent => 1,
+        Priority::Urgent => 3,
+        Priority::Normal => 5,
+        Priority::NotUrgent => 7,
+    }
+}
+
+fn parse_priority(value: &str) -> Option<Priority> {
+    match value.trim().parse::<u8>().ok()? {
+        0 => Some(Priority::ToBeDefined),
+        1..=2 => Some(Priority::VeryUrgent),
+        3..=4 => Some(Priority::Urgent),
+        5 => Some(Priority::Normal),
+        6..=9 => Some(Priority::NotUrgent),
+        _ => None,
+    }
+}
+
+const WEEKDAYS: [(Weekday, &str); 7] = [
+    (Weekday::Mon, "MO"),
+    (Weekday::Tue, "TU"),
+    (Weekday::Wed, "WE"),
+    (Weekday::Thu, "TH"),
+    (Weekday::Fri, "FR"),
+    (Weekday::Sat, "SA"),
+    (Weekday::Sun, "SU"),
+];
+
+// Règle RRULE équivalente à la récurrence ; une répétition après la fin n'en a pas
+fn rrule(recurrence: &Recurrence) -> Option<String> {
+    match recurrence {
+        Recurrence::Daily => Some("FREQ=DAILY".to_string()),
+        Recurrence::Weekly(days) => Some(format!(
+            "FREQ=WEEKLY;BYDAY={}",
+            days.iter()
+                .filter_map(|day| WEEKDAYS.iter().find(|(weekday, _)| weekday == day).map(|(_, code)| *code))
+                .collect::<Vec<_>>()
+                .join(",")
+        )),
+        Recurrence::Monthly(day) => Some(format!("FREQ=MONTHLY;BYMONTHDAY={day}")),
+        Recurrence::AfterCompletion(_) => None,
+    }
+}
+
+fn parse_rrule(value: &str) -> Option<Recurrence> {
+    let parts: HashMap<String, &str> = value
+        .split(';')
+        .filter_map(|part| part.split_once('='))
+        .map(|(key, value)| (key.to_uppercase(), value))
+        .collect();
+    match parts.get("FREQ")?.to_uppercase().as_str() {
+        "DAILY" => Some(Recurrence::Daily),
+        "WEEKLY" => {
+            let days: Vec<Weekday> = parts
+                .get("BYDAY")?
+                .split(',')
+                .filter_map(|code| WEEKDAYS.iter().find(|(_, known)| known.eq_ignore_ascii_case(code.trim())).map(|(day, _)| *day))
+                .collect();
+            (!days.is_empty()).then_some(Recurrence::Weekly(days))
+        }
+        "MONTHLY" => Some(Recurrence::Monthly(parts.get("BYMONTHDAY")?.parse().ok().filter(|day| (1..=31).contains(day))?)),
+        _ => None,
+    }
+}
+
+// texte échappé pour une valeur de propriété
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') | Some('N') => result.push('\n'),
+                Some(other) => result.push(other),
+                None => {}
+            },
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+// ajoute une ligne repliée tous les 75 octets (sans couper un caractère)
+fn push_line(ics: &mut String, line: &str) {
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > LINE_LENGTH {
+            ics.push_str("\r\n ");
+            length = 1;
+        }
+        ics.push(c);
+        length += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn utc(date: NaiveDate) -> String {
+    // date sans heure : minuit heure locale, converti en UTC
+    let local = Local.from_local_datetime(&date.and_time(NaiveTime::MIN)).earliest();
+    local.map(|date| date.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()).unwrap_or_default()
+}
+
+// Calendrier des tâches données ; notes et étiquettes sont indexées par tâche
+pub fn export(name: &str, tasks: &[Task], task_tags: &HashMap<u32, Vec<String>>, notes: &HashMap<u32, String>) -> String {
+    let mut ics = String::new();
+    for line in ["BEGIN:VCALENDAR", "VERSION:2.0", &format!("PRODID:{PRODID}"), "CALSCALE:GREGORIAN"] {
+        push_line(&mut ics, line);
+    }
+    push_line(&mut ics, &format!("X-WR-CALNAME:{}", escape(name)));
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    for task in tasks {
+        push_line(&mut ics, "BEGIN:VTODO");
+        push_line(&mut ics, &format!("UID:task-{}@todo", task.id));
+        push_line(&mut ics, &format!("DTSTAMP:{stamp}"));
+        push_line(&mut ics, &format!("SUMMARY:{}", escape(&task.description)));
+        push_line(&mut ics, &format!("STATUS:{}", ics_status(task.status)));
+        if task.priority != Priority::ToBeDefined {
+            push_line(&mut ics, &format!("PRIORITY:{}", ics_priority(task.priority)));
+        }
+        push_line(&mut ics, &format!("CREATED:{}", utc(task.creation_date)));
+        // DTSTART et DUE doivent être du même type : date seule, ou date et heure locale
+        let with_time = task.due_time.is_some();
+        if let Some(start_date) = task.start_date {
+            match with_time {
+                true => push_line(&mut ics, &format!("DTSTART:{}T000000", start_date.format("%Y%m%d"))),
+                false => push_line(&mut ics, &format!("DTSTART;VALUE=DATE:{}", start_date.format("%Y%m%d"))),
+            }
+        }
+        if let Some(due_date) = task.due_date {
+            match task.due_time {
+                Some(time) => push_line(&mut ics, &format!("DUE:{}T{}", due_date.format("%Y%m%d"), time.format("%H%M%S"))),
+                None => push_line(&mut ics, &format!("DUE;VALUE=DATE:{}", due_date.format("%Y%m%d"))),
+            }
+        }
+        if let Some(completion_date) = task.completion_date.filter(|_| task.status == Status::Finished) {
+            push_line(&mut ics, &format!("COMPLETED:{}", utc(completion_date)));
+            push_line(&mut ics, "PERCENT-COMPLETE:100");
+        }
+        // RRULE demande un DTSTART, la règle de l'application est toujours écrite dans X-TODO-RECURRENCE
+        if let Some(recurrence) = &task.recurrence {
+            if let Some(rule) = rrule(recurrence).filter(|_| task.start_date.is_some()) {
+                push_line(&mut ics, &format!("RRULE:{rule}"));
+            }
+            push_line(&mut ics, &format!("X-TODO-RECURRENCE:{recurrence}"));
+        }
+        if !task.grouping.is_empty() {
+            push_line(&mut ics, &format!("X-TODO-PROJECT:{}", escape(&task.grouping)));
+        }
+        if let Some(tags) = task_tags.get(&task.id).filter(|tags| !tags.is_empty()) {
+            push_line(&mut ics, &format!("CATEGORIES:{}", tags.iter().map(|tag| escape(tag)).collect::<Vec<_>>().join(",")));
+        }
+        if let Some(parent_id) = task.parent_id {
+            push_line(&mut ics, &format!("RELATED-TO;RELTYPE=PARENT:task-{parent_id}@todo"));
+        }
+        if let Some(note) = notes.get(&task.id).filter(|note| !note.trim().is_empty()) {
+            push_line(&mut ics, &format!("DESCRIPTION:{}", escape(note)));
+        }
+        push_line(&mut ics, "END:VTODO");
+    }
+    push_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+// Une propriété lue : nom, paramètres (NOM=valeur) et valeur encore échappée
+
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+// lignes dépliées du fichier avec le numéro de leur première ligne
+fn unfold(content: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = vec![];
+    for (i, line) in content.lines().enumerate() {
+        match (line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')), lines.last_mut()) {
+            (Some(rest), Some((_, last))) => last.push_str(rest),
+            _ => lines.push((i + 1, line.to_string())),
+        }
+    }
+    lines
+}
+
+// morceaux du texte séparés par le caractère donné, hors des valeurs entre guillemets
+// (un paramètre comme TZID="Europe/Paris:x" peut contenir « : » ou « ; »)
+fn split_unquoted(text: &str, separator: char, limit: usize) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut quoted = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted && parts.len() + 1 < limit => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+fn parse_property(line: &str) -> Option<Property> {
+    let [head, value] = split_unquoted(line, ':', 2)[..] else { return None };
+    let mut parts = split_unquoted(head, ';', usize::MAX).into_iter();
+    let name = parts.next()?.trim().to_uppercase();
+    let params = parts
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| (key.to_uppercase(), value.trim_matches('"').to_string()))
+        .collect();
+    Some(Property { name, params, value: value.to_string() })
+}
+
+// date d'une propriété DATE ou DATE-TIME (en UTC si elle finit par Z, convertie en heure locale)
+fn parse_date_time(value: &str) -> Option<(NaiveDate, Option<NaiveTime>)> {
+    let value = value.trim();
+    if let Some(utc) = value.strip_suffix(['Z', 'z']) {
+        let date_time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        let local = Utc.from_utc_datetime(&date_time).with_timezone(&Local).naive_local();
+        return Some((local.date(), Some(local.time())));
+    }
+    match NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        Ok(date_time) => Some((date_time.date(), Some(date_time.time()))),
+        Err(_) => NaiveDate::parse_from_str(value, "%Y%m%d").ok().map(|date| (date, None)),
+    }
+}
+
+// Lit les VTODO d'un fichier iCalendar, une ligne d'import par tâche avec ses erreurs,
+// et la note de chaque tâche (DESCRIPTION) dans le même ordre
+pub fn parse(content: &str) -> (Vec<ImportRow>, Vec<Option<String>>) {
+    let mut rows = vec![];
+    let mut notes = vec![];
+    let mut current: Option<(ImportRow, Option<String>)> = None;
+    for (line_number, line) in unfold(content.trim_start_matches('\u{feff}')) {
+        let Some(property) = parse_property(&line) else { continue };
+        match (property.name.as_str(), property.value.trim().to_uppercase().as_str()) {
+            ("BEGIN", "VTODO") => {
+                let row = ImportRow {
+                    line: line_number,
+                    task: Task { description: String::new(), ..Task::default() },
+                    project: String::new(),
+                    tags: vec![],
+                    errors: vec![],
+                };
+                current = Some((row, None));
+                continue;
+            }
+            ("END", "VTODO") => {
+                if let Some((mut row, note)) = current.take() {
+                    if row.task.description.is_empty() {
+                        row.errors.push("SUMMARY (description) manquant".to_string());
+                    }
+                    rows.push(row);
+                    notes.push(note);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        let Some((row, note)) = current.as_mut() else { continue };
+        let value = property.value.trim();
+        let invalid = || format!("{} : valeur invalide « {value} »", property.name);
+        let task = &mut row.task;
+        match property.name.as_str() {
+            "SUMMARY" => task.description = unescape(value).trim().to_string(),
+            "STATUS" => match parse_status(value) {
+                Some(status) => task.status = status,
+                None => row.errors.push(invalid()),
+            },
+            "PRIORITY" => match parse_priority(value) {
+                Some(priority) => task.priority = priority,
+                None => row.errors.push(invalid()),
+            },
+            "CREATED" | "DTSTART" | "DUE" | "COMPLETED" => match parse_date_time(value) {
+                Some((date, time)) => match property.name.as_str() {
+                    "CREATED" => task.creation_date = date,
+                    "DTSTART" => task.start_date = Some(date),
+                    "COMPLETED" => task.completion_date = Some(date),
+                    _ => {
+                        task.due_date = Some(date);
+                        let date_only = property.params.iter().any(|(key, value)| key == "VALUE" && value.eq_ignore_ascii_case("DATE"));
+                        task.due_time = time.filter(|_| !date_only);
+                    }
+                },
+                None => row.errors.push(invalid()),
+            },
+            "RRULE" if task.recurrence.is_none() => match parse_rrule(value) {
+                Some(recurrence) => task.recurrence = Some(recurrence),
+                None => row.errors.push(format!("RRULE : règle non prise en charge « {value} »")),
+            },
+            "X-TODO-RECURRENCE" => match value.parse() {
+                Ok(recurrence) => task.recurrence = Some(recurrence),
+                Err(message) => row.errors.push(message),
+            },
+            "X-TODO-PROJECT" => row.project = unescape(value).trim().to_string(),
+            "CATEGORIES" => row.tags = parse_tags(&format!("{},{}", row.tags.join(","), unescape(value))),
+            "DESCRIPTION" => *note = Some(unescape(value)),
+            _ => {}
+        }
+    }
+    (rows, notes)
+}
+
+// Importe les VTODO du fichier et leurs notes, tout ou rien. Retourne les tâches créées,
+// ou les erreurs précédées du numéro de ligne du VTODO concerné
+pub fn import(content: &str, conn: &Connection) -> Result<std::result::Result<Vec<Task>, Vec<String>>> {
+    let (rows, notes) = parse(content);
+    if rows.is_empty() {
+        return Ok(Err(vec!["Aucune tâche (VTODO) dans le fichier".to_string()]));
+    }
+    let errors = csv_transfer::row_errors(&rows);
+    if !errors.is_empty() {
+        return Ok(Err(errors));
+    }
+    let tx = Savepoint::new(conn)?;
+    let ids = csv_transfer::import(&rows, &tx)?;
+    for (id, note) in ids.iter().zip(notes) {
+        if let Some(note) = note {
+            Note::save(*id, &note, &tx)?;
+        }
+    }
+    tx.commit()?;
+    let tasks = ids.into_iter().map(|id| Task::get_by_id(id, conn)).collect::<Result<Vec<_>>>()?;
+    Ok(Ok(tasks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let tasks = vec![
+            Task {
+                id: 1,
+                description: "Réunion ; budget, « 2027 »".to_string(),
+                priority: Priority::Urgent,
+                status: Status::InProgress,
+                creation_date: date("2026-09-01"),
+                start_date: Some(date("2026-09-02")),
+                due_date: Some(date("2026-09-10")),
+                due_time: NaiveTime::from_hms_opt(14, 30, 0),
+                recurrence: Some(Recurrence::Weekly(vec![Weekday::Mon, Weekday::Thu])),
+                grouping: "Maison".to_string(),
+                ..Task::default()
+            },
+            Task {
+                id: 2,
+                description: "Payer la facture".to_string(),
+                status: Status::Finished,
+                creation_date: date("2026-08-20"),
+                completion_date: Some(date("2026-08-25")),
+                due_date: Some(date("2026-08-31")),
+                recurrence: Some(Recurrence::AfterCompletion(10)),
+                parent_id: Some(1),
+                ..Task::default()
+            },
+        ];
+        let task_tags = HashMap::from([(1, vec!["au bureau".to_string(), "a,b".to_string()])]);
+        // note plus longue qu'une ligne, pour passer par le repli
+        let note = ["Première ligne ; seconde : « détails », fin"; 4].join("\n");
+        let notes = HashMap::from([(1, note.clone())]);
+
+        let (rows, read_notes) = parse(&export("Toutes", &tasks, &task_tags, &notes));
+        assert_eq!(rows.len(), 2);
+        assert_eq!(read_notes, vec![Some(note), None]);
+        for (task, row) in tasks.iter().zip(&rows) {
+            assert!(row.errors.is_empty(), "{:?}", row.errors);
+            assert_eq!(row.task.description, task.description);
+            assert_eq!(row.task.status, task.status);
+            assert_eq!(row.task.priority, task.priority);
+            assert_eq!(row.task.creation_date, task.creation_date);
+            assert_eq!(row.task.start_date, task.start_date);
+            assert_eq!(row.task.completion_date, task.completion_date);
+            assert_eq!(row.task.due_date, task.due_date);
+            assert_eq!(row.task.due_time, task.due_time);
+            assert_eq!(row.task.recurrence, task.recurrence);
+            assert_eq!(row.project, task.grouping);
+        }
+        assert_eq!(rows[0].tags, vec!["au bureau".to_string(), "a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn folded_lines_stay_short() {
+        let task = Task { description: "é".repeat(100), ..Task::default() };
+        let ics = export("Toutes", &[task], &HashMap::new(), &HashMap::new());
+        assert!(ics.split("\r\n").all(|line| line.len() <= LINE_LENGTH));
+        assert_eq!(parse(&ics).0[0].task.description, "é".repeat(100));
+    }
+
+    #[test]
+    fn quoted_parameter() {
+        let property = parse_property("DUE;TZID=\"Europe/Paris:x;y\";VALUE=DATE-TIME:20260910T143000").unwrap();
+        assert_eq!(property.name, "DUE");
+        assert_eq!(
+            property.params,
+            vec![("TZID".to_string(), "Europe/Paris:x;y".to_string()), ("VALUE".to_string(), "DATE-TIME".to_string())]
+        );
+        assert_eq!(property.value, "20260910T143000");
+        assert!(parse_property("SANS VALEUR").is_none());
+    }
+
+    #[test]
+    fn rrule() {
+        for recurrence in [Recurrence::Daily, Recurrence::Weekly(vec![Weekday::Tue, Weekday::Sun]), Recurrence::Monthly(31)] {
+            assert_eq!(parse_rrule(&super::rrule(&recurrence).unwrap()), Some(recurrence));
+        }
+        assert_eq!(parse_rrule("FREQ=YEARLY"), None);
+    }
+
+    #[test]
+    fn invalid_values() {
+        let ics = "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nSTATUS:PERDU\r\nDUE:demain\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+        let (rows, _) = parse(ics);
+        assert_eq!(rows[0].line, 2);
+        assert_eq!(rows[0].errors.len(), 3);
+    }
+}
@@ -0,0 +1,374 @@
+use crate::enum_with_strings;
+use crate::models::project::Project;
+use crate::models::recurrence::Recurrence;
+use crate::models::savepoint::Savepoint;
+use crate::models::tag::{Tag, parse_tags};
+use crate::models::task::{Duration, Importance, Priority, Status, Task};
+use crate::utils::{parse_optional_date, parse_optional_time};
+
+use rusqlite::{Connection, Result};
+use rusqlite::types::{FromSql, FromSqlError, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+// Import et export des tâches au format CSV (tableurs). Les valeurs d'enum sont exportées
+// avec le nom de la variante et le libellé affiché ; à l'import les deux sont acceptés
+
+enum_with_strings!(CsvField {
+    Description => "Description",
+    Status => "Statut",
+    Priority => "Priorité",
+    Importance => "Importance",
+    Duration => "Durée",
+    Project => "Projet",
+    Tags => "Étiquettes",
+    CreationDate => "Créé le",
+    StartDate => "Commencé le",
+    CompletionDate => "Terminé le",
+    DueDate => "Échéance",
+    DueTime => "Heure d'échéance",
+    Recurrence => "Récurrence",
+});
+
+// Une ligne du fichier importé : la tâche lue et les erreurs trouvées
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportRow {
+    // numéro de ligne dans le fichier (l'en-tête est la ligne 1)
+    pub line: usize,
+    pub task: Task,
+    pub project: String,
+    pub tags: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+// Aperçu d'un import : les colonnes du fichier, le champ associé à chacune et les lignes
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CsvImport {
+    pub headers: Vec<String>,
+    pub mapping: Vec<Option<CsvField>>,
+    pub rows: Vec<ImportRow>,
+    // erreurs qui concernent tout le fichier (colonnes)
+    pub errors: Vec<String>,
+    // projets qui seront créés par l'import
+    pub new_projects: Vec<String>,
+}
+
+impl CsvImport {
+    pub fn error_count(&self) -> usize {
+        self.errors.len() + self.rows.iter().filter(|row| !row.errors.is_empty()).count()
+    }
+}
+
+fn variant_key(value: impl Debug) -> String {
+    format!("{value:?}")
+}
+
+impl CsvField {
+    // champ à partir du nom de sa variante (valeur des listes de correspondance)
+    pub fn from_name(name: &str) -> Option<CsvField> {
+        CsvField::all().into_iter().map(|(field, _)| field).find(|field| variant_key(field) == name)
+    }
+}
+
+// valeur d'enum lue à partir du nom de la variante ou du libellé (casse ignorée)
+fn parse_enum<T: Copy + Debug>(all: Vec<(T, String)>, value: &str) -> Option<T> {
+    let value = value.trim().to_lowercase();
+    all.into_iter()
+        .find(|(variant, label)| variant_key(variant).to_lowercase() == value || label.to_lowercase() == value)
+        .map(|(variant, _)| variant)
+}
+
+// Colonne associée à un en-tête : le nom du champ (creation_date, CreationDate) ou son libellé.
+// Les colonnes de libellé de l'export (priority_label...) sont ignorées
+fn guess_field(header: &str) -> Option<CsvField> {
+    let normalized = |text: &str| text.to_lowercase().replace(['_', ' '], "");
+    let header = normalized(header);
+    CsvField::all()
+        .into_iter()
+        .find(|(field, label)| normalized(&variant_key(field)) == header || normalized(label) == header)
+        .map(|(field, _)| field)
+}
+
+// Séparateur du fichier : le point-virgule des tableurs français s'il est plus présent
+// que la virgule sur la ligne d'en-tête
+fn delimiter(content: &str) -> u8 {
+    let header = content.lines().next().unwrap_or_default();
+    if header.matches(';').count() > header.matches(',').count() { b';' } else { b',' }
+}
+
+// Colonnes de l'export : en-tête et valeur pour une tâche
+fn export_columns(task: &Task, tags: &[String]) -> Vec<(&'static str, String)> {
+    let date = |date: Option<chrono::NaiveDate>| date.map(|date| date.to_string()).unwrap_or_default();
+    vec![
+        ("id", task.id.to_string()),
+        ("description", task.description.clone()),
+        ("status", variant_key(task.status)),
+        ("status_label", task.status.to_string()),
+        ("priority", variant_key(task.priority)),
+        ("priority_label", task.priority.to_string()),
+        ("importance", variant_key(task.importance)),
+        ("importance_label", task.importance.to_string()),
+        ("duration", variant_key(task.duration)),
+        ("duration_label", task.duration.to_string()),
+        ("project", task.grouping.clone()),
+        ("tags", tags.join(", ")),
+        ("creation_date", task.creation_date.to_string()),
+        ("start_date", date(task.start_date)),
+        ("completion_date", date(task.completion_date)),
+        ("due_date", date(task.due_date)),
+        ("due_time", task.due_time.map(|time| time.format("%H:%M").to_string()).unwrap_or_default()),
+        ("recurrence", task.recurrence.as_ref().map(|recurrence| recurrence.to_string()).unwrap_or_default()),
+        ("scoring", task.scoring.to_string()),
+    ]
+}
+
+// fichier CSV des tâches données, précédé de la marque BOM pour qu'Excel le lise en UTF-8
+pub fn export(tasks: &[Task], task_tags: &HashMap<u32, Vec<String>>) -> std::result::Result<String, csv::Error> {
+    let mut writer = csv::Writer::from_writer("\u{feff}".as_bytes().to_vec());
+    let empty = vec![];
+    writer.write_record(export_columns(&Task::default(), &empty).iter().map(|(header, _)| header))?;
+    for task in tasks {
+        let tags = task_tags.get(&task.id).unwrap_or(&empty);
+        writer.write_record(export_columns(task, tags).iter().map(|(_, value)| value))?;
+    }
+    let bytes = writer.into_inner().map_err(|err| err.into_error())?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// lit une valeur de la ligne dans la tâche, retourne le message d'erreur éventuel
+fn read_field(field: CsvField, value: &str, row: &mut ImportRow) -> std::result::Result<(), String> {
+    let value = value.trim();
+    let task = &mut row.task;
+    let invalid = || format!("{} : valeur invalide « {value} »", field);
+    let date = |value: &str| parse_optional_date(value).map_err(|_| invalid());
+    match field {
+        CsvField::Description => task.description = value.to_string(),
+        CsvField::Status if !value.is_empty() => task.status = parse_enum(Status::all(), value).ok_or_else(invalid)?,
+        CsvField::Priority if !value.is_empty() => task.priority = parse_enum(Priority::all(), value).ok_or_else(invalid)?,
+        CsvField::Importance if !value.is_empty() => task.importance = parse_enum(Importance::all(), value).ok_or_else(invalid)?,
+        CsvField::Duration if !value.is_empty() => task.duration = parse_enum(Duration::all(), value).ok_or_else(invalid)?,
+        CsvField::Project => row.project = value.to_string(),
+        CsvField::Tags => row.tags = parse_tags(value),
+        CsvField::CreationDate if !value.is_empty() => task.creation_date = date(value)?.unwrap_or(task.creation_date),
+        CsvField::StartDate => task.start_date = date(value)?,
+        CsvField::CompletionDate => task.completion_date = date(value)?,
+        CsvField::DueDate => task.due_date = date(value)?,
+        CsvField::DueTime => task.due_time = parse_optional_time(value).map_err(|_| invalid())?,
+        CsvField::Recurrence if !value.is_empty() => task.recurrence = Some(value.parse::<Recurrence>()?),
+        _ => {}
+    }
+    Ok(())
+}
+
+// Lit le fichier et vérifie chaque ligne sans rien enregistrer. Sans correspondance
+// fournie, les colonnes sont associées aux champs d'après leur en-tête
+pub fn parse(content: &str, mapping: Option<Vec<Option<CsvField>>>, conn: &Connection) -> Result<CsvImport> {
+    let content = content.trim_start_matches('\u{feff}');
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter(content))
+        .flexible(true)
+        .from_reader(content.as_bytes());
+    let headers: Vec<String> = reader.headers().map(|headers| headers.iter().map(str::to_string).collect()).unwrap_or_default();
+    let mut mapping = mapping.unwrap_or_else(|| headers.iter().map(|header| guess_field(header)).collect());
+    mapping.resize(headers.len(), None);
+
+    let mut errors = vec![];
+    if headers.is_empty() {
+        errors.push("Le fichier est vide".to_string());
+    } else if !mapping.contains(&Some(CsvField::Description)) {
+        errors.push("Aucune colonne n'est associée à la description".to_string());
+    }
+    for (field, label) in CsvField::all() {
+        if mapping.iter().filter(|mapped| **mapped == Some(field)).count() > 1 {
+            errors.push(format!("Plusieurs colonnes sont associées à « {label} »"));
+        }
+    }
+
+    let mut rows = vec![];
+    for (i, record) in reader.records().enumerate() {
+        let line = i + 2;
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                errors.push(format!("Ligne {line} : {err}"));
+                continue;
+            }
+        };
+        if record.iter().all(|value| value.trim().is_empty()) {
+            continue;
+        }
+        let mut row = ImportRow {
+            line,
+            task: Task { description: String::new(), ..Task::default() },
+            project: String::new(),
+            tags: vec![],
+            errors: vec![],
+        };
+        for (field, value) in mapping.iter().zip(record.iter()) {
+            if let Some(field) = field
+                && let Err(message) = read_field(*field, value, &mut row)
+            {
+                row.errors.push(message);
+            }
+        }
+        if row.task.description.is_empty() {
+            row.errors.push("La description est obligatoire".to_string());
+        }
+        rows.push(row);
+    }
+    if rows.is_empty() && errors.is_empty() {
+        errors.push("Aucune tâche à importer".to_string());
+    }
+
+    let projects = Project::get_all(true, conn)?;
+    let mut new_projects: Vec<String> = vec![];
+    for row in &rows {
+        let known = |name: &String| name.to_lowercase() == row.project.to_lowercase();
+        if !row.project.is_empty() && !projects.iter().any(|project| known(&project.name)) && !new_projects.iter().any(known) {
+            new_projects.push(row.project.clone());
+        }
+    }
+
+    Ok(CsvImport { headers, mapping, rows, errors, new_projects })
+}
+
+// erreurs des lignes lues, précédées du numéro de ligne
+pub fn row_errors(rows: &[ImportRow]) -> Vec<String> {
+    rows.iter()
+        .flat_map(|row| row.errors.iter().map(move |error| format!("Ligne {} : {error}", row.line)))
+        .collect()
+}
+
+// Enregistre les lignes vérifiées dans une seule transaction, en créant les projets
+// manquants. Retourne les identifiants des tâches créées
+pub fn import(rows: &[ImportRow], conn: &Connection) -> Result<Vec<u32>> {
+    let tx = Savepoint::new(conn)?;
+    let mut projects = Project::get_all(true, &tx)?;
+    let mut ids = vec![];
+    for row in rows {
+        let mut task = row.task.clone();
+        if !row.project.is_empty() {
+            let project_id = match projects.iter().find(|project| project.name.to_lowercase() == row.project.to_lowercase()) {
+                Some(project) => project.id,
+                None => {
+                    let mut project = Project { name: row.project.clone(), ..Project::default() };
+                    project.insert(&tx)?;
+                    projects.push(project.clone());
+                    project.id
+                }
+            };
+            task.project_id = Some(project_id);
+        }
+        task.insert(&tx)?;
+        // update_date a pu remplacer les dates du fichier par celle du jour
+        if row.task.start_date.is_some() || row.task.completion_date.is_some() {
+            Task::set_dates(
+                task.id,
+                row.task.start_date.or(task.start_date),
+                row.task.completion_date.or(task.completion_date),
+                &tx,
+            )?;
+        }
+        Tag::set_for_task(task.id, &row.tags, &tx)?;
+        ids.push(task.id);
+    }
+    tx.commit()?;
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::migration::test_database as database;
+    use chrono::{NaiveDate, NaiveTime};
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn tasks() -> Vec<Task> {
+        vec![
+            Task {
+                id: 1,
+                description: "Devis \"cuisine\", version 2".to_string(),
+                status: Status::InProgress,
+                priority: Priority::Urgent,
+                importance: Importance::VeryImportant,
+                duration: Duration::Long,
+                grouping: "Maison".to_string(),
+                creation_date: date("2026-09-01"),
+                start_date: Some(date("2026-09-02")),
+                due_date: Some(date("2026-09-10")),
+                due_time: NaiveTime::from_hms_opt(9, 15, 0),
+                recurrence: Some(Recurrence::Monthly(15)),
+                ..Task::default()
+            },
+            Task {
+                id: 2,
+                description: "Ligne 1\nligne 2 ; fin".to_string(),
+                status: Status::Finished,
+                creation_date: date("2026-08-20"),
+                completion_date: Some(date("2026-08-25")),
+                ..Task::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn round_trip() {
+        let conn = database();
+        let tasks = tasks();
+        let task_tags = HashMap::from([(1, vec!["au bureau".to_string(), "devis".to_string()])]);
+        let preview = parse(&export(&tasks, &task_tags).unwrap(), None, &conn).unwrap();
+        assert!(preview.errors.is_empty(), "{:?}", preview.errors);
+        assert_eq!(preview.new_projects, vec!["Maison".to_string()]);
+        assert_eq!(preview.rows.len(), 2);
+        for (task, row) in tasks.iter().zip(&preview.rows) {
+            assert!(row.errors.is_empty(), "{:?}", row.errors);
+            assert_eq!(row.task.description, task.description);
+            assert_eq!(row.task.status, task.status);
+            assert_eq!(row.task.priority, task.priority);
+            assert_eq!(row.task.importance, task.importance);
+            assert_eq!(row.task.duration, task.duration);
+            assert_eq!(row.task.creation_date, task.creation_date);
+            assert_eq!(row.task.start_date, task.start_date);
+            assert_eq!(row.task.completion_date, task.completion_date);
+            assert_eq!(row.task.due_date, task.due_date);
+            assert_eq!(row.task.due_time, task.due_time);
+            assert_eq!(row.task.recurrence, task.recurrence);
+            assert_eq!(row.project, task.grouping);
+            assert_eq!(&row.tags, task_tags.get(&task.id).unwrap_or(&vec![]));
+        }
+
+        let ids = import(&preview.rows, &conn).unwrap();
+        let saved = Task::get_by_id(ids[0], &conn).unwrap();
+        assert_eq!(saved.grouping, "Maison");
+        assert_eq!(saved.start_date, tasks[0].start_date);
+        assert_eq!(Task::get_by_id(ids[1], &conn).unwrap().completion_date, tasks[1].completion_date);
+        assert_eq!(Tag::by_task(&conn).unwrap().get(&ids[0]), task_tags.get(&1));
+    }
+
+    // fichier de tableur français : point-virgule et libellés
+    #[test]
+    fn labels_and_semicolon() {
+        let conn = database();
+        let content = "Description;Statut;Priorité;Échéance\nAppeler;En cours;Urgent;2026-10-20\n;;;\nSans;Perdu;;demain\n";
+        let preview = parse(content, None, &conn).unwrap();
+        assert_eq!(preview.mapping, vec![Some(CsvField::Description), Some(CsvField::Status), Some(CsvField::Priority), Some(CsvField::DueDate)]);
+        assert_eq!(preview.rows.len(), 2);
+        assert_eq!(preview.rows[0].task.status, Status::InProgress);
+        assert_eq!(preview.rows[0].task.priority, Priority::Urgent);
+        assert_eq!(preview.rows[1].line, 4);
+        assert_eq!(preview.error_count(), 1);
+        assert_eq!(row_errors(&preview.rows).len(), 2);
+    }
+
+    #[test]
+    fn missing_description_column() {
+        let preview = parse("Statut\nEn cours\n", None, &database()).unwrap();
+        assert!(!preview.errors.is_empty());
+    }
+}
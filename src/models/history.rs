@@ -0,0 +1,191 @@
+use crate::enum_with_strings;
+use crate::models::task::{Status, Task};
+
+use chrono::{Local, NaiveDateTime};
+use rusqlite::{Connection, Result, Row, params};
+use rusqlite::types::{FromSql, FromSqlError, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+
+// Historique des modifications des tâches (table task_events, en ajout seul)
+
+enum_with_strings!(EventKind {
+    Created => "Création",
+    Updated => "Modification",
+    StatusChanged => "Changement de statut",
+    Deleted => "Mise à la corbeille",
+    Restored => "Restauration",
+    Purged => "Suppression définitive",
+});
+
+// Une ligne d'historique : pour une modification, le champ concerné avec l'ancienne
+// et la nouvelle valeur telles qu'affichées dans les vues. Un changement de statut garde
+// aussi les statuts eux-mêmes, indépendants des libellés (flux cumulé des statistiques)
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: u32,
+    pub task_id: u32,
+    pub kind: EventKind,
+    pub field: Option<String>,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    #[serde(default)]
+    pub old_status: Option<Status>,
+    #[serde(default)]
+    pub new_status: Option<Status>,
+    pub created_at: NaiveDateTime,
+}
+
+const SQL_SELECT: &str = "SELECT id, task_id, kind, field, old_value, new_value, old_status, new_status, created_at FROM task_events";
+
+const STATUS_FIELD: &str = "Statut";
+
+// Valeurs affichables des champs suivis, avec leur libellé
+
+fn tracked_fields(task: &Task) -> Vec<(&'static str, String)> {
+    let date = |d: Option<chrono::NaiveDate>| d.map(|d| d.to_string()).unwrap_or_default();
+    vec![
+        ("Description", task.description.clone()),
+        (STATUS_FIELD, task.status.to_string()),
+        ("Priorité", task.priority.to_string()),
+        ("Importance", task.importance.to_string()),
+        ("Durée", task.duration.to_string()),
+        ("Projet", task.grouping.clone()),
+        ("Tâche parente", task.parent_id.map(|id| id.to_string()).unwrap_or_default()),
+        ("Créé le", task.creation_date.to_string()),
+        ("Commencé le", date(task.start_date)),
+        ("Terminé le", date(task.completion_date)),
+        ("Échéance", task.due_date.map(|d| match task.due_time {
+            Some(time) => format!("{d} {}", time.format("%H:%M")),
+            None => d.to_string(),
+        }).unwrap_or_default()),
+        ("Récurrence", task.recurrence.as_ref().map(|recurrence| recurrence.label()).unwrap_or_default()),
+    ]
+}
+
+impl HistoryEntry {
+
+    fn from_row(row: &Row) -> Result<HistoryEntry> {
+        Ok(HistoryEntry {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            kind: row.get("kind")?,
+            field: row.get("field")?,
+            old_value: row.get("old_value")?,
+            new_value: row.get("new_value")?,
+            old_status: row.get("old_status")?,
+            new_status: row.get("new_status")?,
+            created_at: row.get("created_at")?,
+        })
+    }
+
+    fn record(conn: &Connection, task_id: u32, kind: EventKind, field: Option<&str>, old_value: Option<&str>, new_value: Option<&str>) -> Result<()> {
+        HistoryEntry {
+            id: 0,
+            task_id,
+            kind,
+            field: field.map(str::to_string),
+            old_value: old_value.map(str::to_string),
+            new_value: new_value.map(str::to_string),
+            old_status: None,
+            new_status: None,
+            created_at: Local::now().naive_local(),
+        }
+        .insert(task_id, conn)
+    }
+
+    // enregistre la ligne pour la tâche task_id (l'id de la ligne est attribué par la base)
+    pub fn insert(&self, task_id: u32, conn: &Connection) -> Result<()> {
+        conn.execute(
+            "INSERT INTO task_events (task_id, kind, field, old_value, new_value, old_status, new_status, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8);",
+            params![task_id, self.kind, self.field, self.old_value, self.new_value, self.old_status, self.new_status, self.created_at],
+        )?;
+        Ok(())
+    }
+
+    // statuts d'un changement de statut ; ceux d'une sauvegarde antérieure à leur
+    // enregistrement sont retrouvés à partir des libellés
+    pub fn statuses(&self) -> (Option<Status>, Option<Status>) {
+        if self.field.as_deref() != Some(STATUS_FIELD) || self.old_status.is_some() || self.new_status.is_some() {
+            return (self.old_status, self.new_status);
+        }
+        let status = |value: &Option<String>| {
+            Status::all().into_iter().find(|(_, label)| value.as_ref() == Some(label)).map(|(status, _)| status)
+        };
+        (status(&self.old_value), status(&self.new_value))
+    }
+
+    // trace la création d'une tâche
+    pub fn record_created(conn: &Connection, task: &Task) -> Result<()> {
+        HistoryEntry::record(conn, task.id, EventKind::Created, None, None, Some(&task.description))
+    }
+
+    // trace une ligne par champ modifié entre l'ancienne et la nouvelle version de la tâche
+    pub fn record_changes(conn: &Connection, kind: EventKind, old: &Task, new: &Task) -> Result<()> {
+        for ((field, old_value), (_, new_value)) in tracked_fields(old).into_iter().zip(tracked_fields(new)) {
+            if old_value == new_value {
+                continue;
+            }
+            let (old_status, new_status) = match field {
+                STATUS_FIELD => (Some(old.status), Some(new.status)),
+                _ => (None, None),
+            };
+            HistoryEntry {
+                id: 0,
+                task_id: old.id,
+                kind,
+                field: Some(field.to_string()),
+                old_value: Some(old_value),
+                new_value: Some(new_value),
+                old_status,
+                new_status,
+                created_at: Local::now().naive_local(),
+            }
+            .insert(old.id, conn)?;
+        }
+        Ok(())
+    }
+
+    // trace l'ajout (new) ou le retrait (old) d'une dépendance de la tâche
+    pub fn record_dependency(conn: &Connection, task_id: u32, old: Option<&Task>, new: Option<&Task>) -> Result<()> {
+        let label = |task: &Task| format!("#{} {}", task.id, task.description);
+        HistoryEntry::record(conn, task_id, EventKind::Updated, Some("Bloquée par"), old.map(label).as_deref(), new.map(label).as_deref())
+    }
+
+    // trace le changement des étiquettes d'une tâche
+    pub fn record_tags(conn: &Connection, task_id: u32, old: &[String], new: &[String]) -> Result<()> {
+        HistoryEntry::record(conn, task_id, EventKind::Updated, Some("Étiquettes"), Some(&old.join(", ")), Some(&new.join(", ")))
+    }
+
+    // trace la mise à la corbeille d'une tâche
+    pub fn record_deleted(conn: &Connection, task: &Task) -> Result<()> {
+        HistoryEntry::record(conn, task.id, EventKind::Deleted, None, Some(&task.description), None)
+    }
+
+    // trace la sortie de corbeille d'une tâche
+    pub fn record_restored(conn: &Connection, task: &Task) -> Result<()> {
+        HistoryEntry::record(conn, task.id, EventKind::Restored, None, None, Some(&task.description))
+    }
+
+    // trace la suppression définitive d'une tâche (l'historique est conservé)
+    pub fn record_purged(conn: &Connection, task: &Task) -> Result<()> {
+        HistoryEntry::record(conn, task.id, EventKind::Purged, None, Some(&task.description), None)
+    }
+
+    // historique d'une tâche, du plus récent au plus ancien
+    pub fn for_task(task_id: u32, conn: &Connection) -> Result<Vec<HistoryEntry>> {
+        let mut stmt = conn.prepare(&format!("{SQL_SELECT} WHERE task_id = ?1 ORDER BY created_at desc, id desc"))?;
+        let entries = stmt.query_map(params![task_id], HistoryEntry::from_row)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    // événements sur une période (bornes optionnelles et incluses), éventuellement pour une seule tâche
+    pub fn between(conn: &Connection, from: Option<NaiveDateTime>, to: Option<NaiveDateTime>, task_id: Option<u32>) -> Result<Vec<HistoryEntry>> {
+        let mut stmt = conn.prepare(&format!(
+            "{SQL_SELECT} WHERE (?1 IS NULL OR created_at >= ?1) AND (?2 IS NULL OR created_at <= ?2) AND (?3 IS NULL OR task_id = ?3) ORDER BY created_at, id"
+        ))?;
+        let entries = stmt.query_map(params![from, to, task_id], HistoryEntry::from_row)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+}
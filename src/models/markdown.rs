@@ -0,0 +1,62 @@
+use crate::models::task::{Priority, Status, Task, tree_order};
+
+use std::collections::{BTreeMap, HashMap};
+
+// Export des tâches en liste de cases à cocher Markdown, une section par projet
+// (les tâches sans projet à la fin), les sous-tâches indentées sous leur parente
+
+fn item(task: &Task, depth: usize, tags: &[String]) -> String {
+    let mut details: Vec<String> = vec![];
+    if task.status != Status::ToDo && task.status != Status::Finished {
+        details.push(task.status.to_string());
+    }
+    if task.priority != Priority::ToBeDefined {
+        details.push(task.priority.to_string());
+    }
+    if let Some(due_date) = task.due_date {
+        details.push(format!("échéance {}", due_date.format("%d/%m/%Y")));
+    }
+    let mut line = format!(
+        "{}- [{}] {}",
+        "  ".repeat(depth),
+        if task.status.is_resolved() { "x" } else { " " },
+        task.description.split_whitespace().collect::<Vec<_>>().join(" ")
+    );
+    if task.status == Status::Canceled {
+        line = line.replacen("] ", "] ~~", 1) + "~~";
+    }
+    if !details.is_empty() {
+        line.push_str(&format!(" ({})", details.join(", ")));
+    }
+    for tag in tags {
+        line.push_str(&format!(" #{}", tag.split_whitespace().collect::<Vec<_>>().join("_")));
+    }
+    line
+}
+
+// document Markdown des tâches données, sous le titre indiqué
+pub fn export(title: &str, tasks: Vec<Task>, task_tags: &HashMap<u32, Vec<String>>) -> String {
+    let mut by_project: BTreeMap<String, Vec<Task>> = BTreeMap::new();
+    let mut without_project = vec![];
+    for task in tasks {
+        match task.grouping.is_empty() {
+            true => without_project.push(task),
+            false => by_project.entry(task.grouping.clone()).or_default().push(task),
+        }
+    }
+    let mut sections: Vec<(String, Vec<Task>)> = by_project.into_iter().collect();
+    if !without_project.is_empty() {
+        sections.push(("Sans projet".to_string(), without_project));
+    }
+
+    let empty = vec![];
+    let mut markdown = format!("# {title}\n");
+    for (project, tasks) in sections {
+        markdown.push_str(&format!("\n## {project}\n\n"));
+        for (task, depth) in tree_order(tasks) {
+            markdown.push_str(&item(&task, depth, task_tags.get(&task.id).unwrap_or(&empty)));
+            markdown.push('\n');
+        }
+    }
+    markdown
+}
@@ -0,0 +1,60 @@
+use crate::enum_with_strings;
+use crate::models::task::{Importance, Priority, Task};
+
+use rusqlite::Result;
+use rusqlite::types::{FromSql, FromSqlError, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+
+// Matrice d'Eisenhower : la priorité donne l'urgence et l'importance l'axe important,
+// une tâche dont l'une des deux valeurs est à définir n'est dans aucun quadrant
+
+enum_with_strings!(Quadrant {
+    Do => "Faire",
+    Schedule => "Planifier",
+    Delegate => "Déléguer",
+    Drop => "Abandonner",
+});
+
+fn is_urgent(priority: Priority) -> bool {
+    matches!(priority, Priority::Urgent | Priority::VeryUrgent)
+}
+
+fn is_important(importance: Importance) -> bool {
+    matches!(importance, Importance::Important | Importance::VeryImportant)
+}
+
+impl Quadrant {
+
+    fn urgent(&self) -> bool {
+        matches!(self, Quadrant::Do | Quadrant::Delegate)
+    }
+
+    fn important(&self) -> bool {
+        matches!(self, Quadrant::Do | Quadrant::Schedule)
+    }
+
+    // quadrant de la tâche, None si elle n'est pas classée
+    pub fn of(task: &Task) -> Option<Quadrant> {
+        if task.priority == Priority::ToBeDefined || task.importance == Importance::ToBeDefined {
+            return None;
+        }
+        Some(match (is_urgent(task.priority), is_important(task.importance)) {
+            (true, true) => Quadrant::Do,
+            (false, true) => Quadrant::Schedule,
+            (true, false) => Quadrant::Delegate,
+            (false, false) => Quadrant::Drop,
+        })
+    }
+
+    // place la tâche dans le quadrant : seul l'axe qui change est modifié, une tâche très
+    // urgente déplacée de Faire à Déléguer reste très urgente
+    pub fn apply(&self, task: &mut Task) {
+        if task.priority == Priority::ToBeDefined || is_urgent(task.priority) != self.urgent() {
+            task.priority = if self.urgent() { Priority::Urgent } else { Priority::NotUrgent };
+        }
+        if task.importance == Importance::ToBeDefined || is_important(task.importance) != self.important() {
+            task.importance = if self.important() { Importance::Important } else { Importance::NotImportant };
+        }
+    }
+
+}
@@ -0,0 +1,255 @@
+use rusqlite::{Connection, Result};
+
+// Liste ordonnée des migrations du schéma : la migration d'indice i fait passer la base
+// de la version i à la version i+1. On ne modifie jamais une migration déjà publiée,
+// on en ajoute une nouvelle à la fin.
+
+const MIGRATIONS: &[&str] = &[
+    // 1 : schéma initial (compatible avec les bases créées avant les migrations)
+    "CREATE TABLE IF NOT EXISTS tasks (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        description TEXT,
+        priority INTEGER,
+        importance INTEGER,
+        duration INTEGER,
+        creation_date TEXT,
+        completion_date TEXT,
+        start_date TEXT,
+        status INTEGER,
+        grouping TEXT,
+        scoring INTEGER
+    );",
+    // 2 : index plein texte des tâches, tenu à jour par des triggers sur tasks
+    "CREATE VIRTUAL TABLE tasks_fts USING fts5(description, grouping, notes, tokenize = 'unicode61 remove_diacritics 2');
+    INSERT INTO tasks_fts (rowid, description, grouping, notes) SELECT id, description, grouping, '' FROM tasks;
+    CREATE TRIGGER tasks_fts_insert AFTER INSERT ON tasks BEGIN
+        INSERT INTO tasks_fts (rowid, description, grouping, notes) VALUES (new.id, new.description, new.grouping, '');
+    END;
+    CREATE TRIGGER tasks_fts_update AFTER UPDATE OF description, grouping ON tasks BEGIN
+        UPDATE tasks_fts SET description = new.description, grouping = new.grouping WHERE rowid = new.id;
+    END;
+    CREATE TRIGGER tasks_fts_delete AFTER DELETE ON tasks BEGIN
+        DELETE FROM tasks_fts WHERE rowid = old.id;
+    END;",
+    // 3 : notes des tâches, indexées dans tasks_fts et supprimées avec leur tâche
+    "CREATE TABLE notes (
+        task_id INTEGER PRIMARY KEY REFERENCES tasks (id) ON DELETE CASCADE,
+        content TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+    CREATE TRIGGER notes_fts_insert AFTER INSERT ON notes BEGIN
+        UPDATE tasks_fts SET notes = new.content WHERE rowid = new.task_id;
+    END;
+    CREATE TRIGGER notes_fts_update AFTER UPDATE OF content ON notes BEGIN
+        UPDATE tasks_fts SET notes = new.content WHERE rowid = new.task_id;
+    END;
+    CREATE TRIGGER tasks_notes_delete AFTER DELETE ON tasks BEGIN
+        DELETE FROM notes WHERE task_id = old.id;
+    END;",
+    // 4 : historique des modifications, conservé après la suppression de la tâche
+    "CREATE TABLE task_events (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        task_id INTEGER NOT NULL,
+        kind INTEGER NOT NULL,
+        field TEXT,
+        old_value TEXT,
+        new_value TEXT,
+        created_at TEXT NOT NULL
+    );
+    CREATE INDEX task_events_task ON task_events (task_id, created_at);
+    CREATE INDEX task_events_date ON task_events (created_at);",
+    // 5 : corbeille, les tâches supprimées sont marquées avant d'être purgées
+    "ALTER TABLE tasks ADD COLUMN deleted_at TEXT;
+    CREATE INDEX tasks_deleted_at ON tasks (deleted_at);",
+    // 6 : sous-tâches
+    "ALTER TABLE tasks ADD COLUMN parent_id INTEGER REFERENCES tasks (id);
+    CREATE INDEX tasks_parent ON tasks (parent_id);",
+    // 7 : dépendances entre tâches (task_id attend blocker_id)
+    "CREATE TABLE task_dependencies (
+        task_id INTEGER NOT NULL REFERENCES tasks (id),
+        blocker_id INTEGER NOT NULL REFERENCES tasks (id),
+        PRIMARY KEY (task_id, blocker_id)
+    );
+    CREATE INDEX task_dependencies_blocker ON task_dependencies (blocker_id);",
+    // 8 : projets, créés à partir des regroupements saisis librement ; le nom du projet
+    // remplace le regroupement dans l'index plein texte
+    "CREATE TABLE projects (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL UNIQUE,
+        color TEXT NOT NULL DEFAULT '#6c757d',
+        description TEXT NOT NULL DEFAULT '',
+        archived INTEGER NOT NULL DEFAULT 0
+    );
+    INSERT OR IGNORE INTO projects (name) SELECT DISTINCT trim(grouping) FROM tasks WHERE trim(coalesce(grouping, '')) != '' ORDER BY 1;
+    ALTER TABLE tasks ADD COLUMN project_id INTEGER REFERENCES projects (id);
+    UPDATE tasks SET project_id = (SELECT id FROM projects WHERE name = trim(tasks.grouping));
+    CREATE INDEX tasks_project ON tasks (project_id);
+    UPDATE tasks_fts SET grouping = coalesce((SELECT projects.name FROM tasks JOIN projects ON projects.id = tasks.project_id WHERE tasks.id = tasks_fts.rowid), '');
+    DROP TRIGGER tasks_fts_insert;
+    DROP TRIGGER tasks_fts_update;
+    ALTER TABLE tasks DROP COLUMN grouping;
+    CREATE TRIGGER tasks_fts_insert AFTER INSERT ON tasks BEGIN
+        INSERT INTO tasks_fts (rowid, description, grouping, notes)
+        VALUES (new.id, new.description, coalesce((SELECT name FROM projects WHERE id = new.project_id), ''), '');
+    END;
+    CREATE TRIGGER tasks_fts_update AFTER UPDATE OF description, project_id ON tasks BEGIN
+        UPDATE tasks_fts SET description = new.description,
+            grouping = coalesce((SELECT name FROM projects WHERE id = new.project_id), '')
+        WHERE rowid = new.id;
+    END;
+    CREATE TRIGGER projects_fts_update AFTER UPDATE OF name ON projects BEGIN
+        UPDATE tasks_fts SET grouping = new.name WHERE rowid IN (SELECT id FROM tasks WHERE project_id = new.id);
+    END;",
+    // 9 : étiquettes, plusieurs par tâche
+    "CREATE TABLE tags (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL UNIQUE COLLATE NOCASE
+    );
+    CREATE TABLE task_tags (
+        task_id INTEGER NOT NULL REFERENCES tasks (id),
+        tag_id INTEGER NOT NULL REFERENCES tags (id),
+        PRIMARY KEY (task_id, tag_id)
+    );
+    CREATE INDEX task_tags_tag ON task_tags (tag_id);
+    CREATE TRIGGER tasks_tags_delete AFTER DELETE ON tasks BEGIN
+        DELETE FROM task_tags WHERE task_id = old.id;
+    END;",
+    // 10 : échéance (date et heure facultative)
+    "ALTER TABLE tasks ADD COLUMN due_date TEXT;
+    ALTER TABLE tasks ADD COLUMN due_time TEXT;
+    CREATE INDEX tasks_due_date ON tasks (due_date);",
+    // 11 : tâches récurrentes, chaque occurrence garde le lien vers la précédente
+    "ALTER TABLE tasks ADD COLUMN recurrence TEXT;
+    ALTER TABLE tasks ADD COLUMN previous_id INTEGER REFERENCES tasks (id);
+    CREATE INDEX tasks_previous ON tasks (previous_id);",
+    // 12 : poids du calcul du score modifiés par rapport aux valeurs par défaut
+    "CREATE TABLE scoring_weights (
+        factor INTEGER NOT NULL,
+        variant TEXT NOT NULL,
+        points INTEGER NOT NULL,
+        PRIMARY KEY (factor, variant)
+    );",
+    // 13 : temps passé sur les tâches, une ligne par démarrage du chronomètre
+    "CREATE TABLE time_entries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        task_id INTEGER NOT NULL REFERENCES tasks (id),
+        started_at TEXT NOT NULL,
+        stopped_at TEXT
+    );
+    CREATE INDEX time_entries_task ON time_entries (task_id);
+    CREATE INDEX time_entries_started ON time_entries (started_at);
+    CREATE TRIGGER tasks_time_entries_delete AFTER DELETE ON tasks BEGIN
+        DELETE FROM time_entries WHERE task_id = old.id;
+    END;",
+    // 14 : statuts d'un changement de statut enregistrés comme valeurs de l'enum (et non
+    // plus seulement comme libellés affichés), repris des libellés pour l'historique existant
+    "ALTER TABLE task_events ADD COLUMN old_status INTEGER;
+    ALTER TABLE task_events ADD COLUMN new_status INTEGER;
+    UPDATE task_events SET
+        old_status = CASE old_value WHEN 'A faire' THEN 0 WHEN 'En cours' THEN 1 WHEN 'Fini' THEN 2 WHEN 'Annulé' THEN 3 WHEN 'Bloqué' THEN 4 END,
+        new_status = CASE new_value WHEN 'A faire' THEN 0 WHEN 'En cours' THEN 1 WHEN 'Fini' THEN 2 WHEN 'Annulé' THEN 3 WHEN 'Bloqué' THEN 4 END
+    WHERE field = 'Statut';",
+    // 15 : répétition après la fin limitée à 3650 jours, les règles plus longues déjà
+    // enregistrées sont ramenées à cette limite pour rester lisibles
+    "UPDATE tasks SET recurrence = 'after:3650'
+    WHERE recurrence LIKE 'after:%' AND CAST(substr(recurrence, 7) AS INTEGER) > 3650;",
+];
+
+// Version du schéma stockée dans la base (PRAGMA user_version)
+
+pub fn version(conn: &Connection) -> Result<usize> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+// Applique dans une transaction chacune des migrations pas encore passées sur la base
+
+pub fn run(conn: &mut Connection) -> Result<()> {
+    let current = version(conn)?;
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(current) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+        // sur la sortie d'erreur : la sortie standard des sous-commandes peut être redirigée
+        eprintln!("Migration {} appliquée", i + 1);
+    }
+    Ok(())
+}
+
+// Base en mémoire à jour, clés étrangères activées comme à l'ouverture de la base
+#[cfg(test)]
+pub fn test_database() -> Connection {
+    let mut conn = Connection::open_in_memory().unwrap();
+    conn.pragma_update(None, "foreign_keys", true).unwrap();
+    run(&mut conn).unwrap();
+    conn
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::task::{Status, Task};
+
+    // base arrêtée à la version donnée, comme une base créée par une version précédente
+    fn database_at(version: usize) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        for sql in &MIGRATIONS[..version] {
+            conn.execute_batch(sql).unwrap();
+        }
+        conn.pragma_update(None, "user_version", version).unwrap();
+        conn
+    }
+
+    #[test]
+    fn new_database() {
+        let mut conn = test_database();
+        assert_eq!(version(&conn).unwrap(), MIGRATIONS.len());
+        // une seconde exécution ne rejoue rien
+        run(&mut conn).unwrap();
+        assert_eq!(version(&conn).unwrap(), MIGRATIONS.len());
+    }
+
+    // base d'avant les migrations : les regroupements deviennent des projets
+    #[test]
+    fn legacy_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.execute_batch(
+            "INSERT INTO tasks (description, priority, importance, duration, creation_date, status, grouping, scoring)
+            VALUES ('Appeler le plombier', 3, 2, 3, '2024-05-01', 0, ' Maison ', 18),
+                ('Sans projet', 0, 0, 0, '2024-05-02', 2, '', 3);",
+        )
+        .unwrap();
+        run(&mut conn).unwrap();
+        let task = Task::get_by_id(1, &conn).unwrap();
+        assert_eq!(task.grouping, "Maison");
+        assert_eq!(Task::get_by_id(2, &conn).unwrap().project_id, None);
+        let hits = Task::search(&conn, "maison").unwrap();
+        assert_eq!(hits.iter().map(|hit| hit.task.id).collect::<Vec<_>>(), vec![1]);
+    }
+
+    // les changements de statut déjà enregistrés retrouvent leurs valeurs d'enum
+    #[test]
+    fn status_backfill() {
+        let mut conn = database_at(13);
+        conn.execute_batch(
+            "INSERT INTO task_events (task_id, kind, field, old_value, new_value, created_at)
+            VALUES (1, 1, 'Statut', 'A faire', 'En cours', '2026-01-01 10:00:00'),
+                (1, 1, 'Statut', 'Bloqué', 'Annulé', '2026-01-02 10:00:00'),
+                (1, 1, 'Description', 'A faire', 'Fini', '2026-01-03 10:00:00');",
+        )
+        .unwrap();
+        run(&mut conn).unwrap();
+        let mut stmt = conn.prepare("SELECT old_status, new_status FROM task_events ORDER BY id").unwrap();
+        let statuses: Vec<(Option<Status>, Option<Status>)> =
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap().collect::<Result<_>>().unwrap();
+        assert_eq!(
+            statuses,
+            vec![
+                (Some(Status::ToDo), Some(Status::InProgress)),
+                (Some(Status::Blocked), Some(Status::Canceled)),
+                (None, None),
+            ]
+        );
+    }
+}
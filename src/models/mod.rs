@@ -1 +1,17 @@
+pub mod backup;
+pub mod calendar;
+pub mod csv_transfer;
+pub mod history;
+pub mod markdown;
+pub mod matrix;
+pub mod migration;
+pub mod note;
+pub mod project;
+pub mod recurrence;
+pub mod savepoint;
+pub mod scoring;
+pub mod stats;
+pub mod tag;
 pub mod task;
+pub mod time_entry;
+pub mod todo_txt;
\ No newline at end of file
@@ -0,0 +1,55 @@
+use chrono::{Local, NaiveDateTime};
+use rusqlite::{Connection, OptionalExtension, Result, params};
+use serde::{Deserialize, Serialize};
+
+// Notes libres associées à une tâche (une note par tâche)
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Note {
+    pub task_id: u32,
+    pub content: String,
+    pub updated_at: NaiveDateTime,
+}
+
+impl Note {
+
+    // lit la note d'une tâche, None si aucune note n'a encore été saisie
+    pub fn get(task_id: u32, conn: &Connection) -> Result<Option<Note>> {
+        conn.query_row(
+            "SELECT task_id, content, updated_at FROM notes WHERE task_id = ?1",
+            params![task_id],
+            |row| Ok(Note {
+                task_id: row.get("task_id")?,
+                content: row.get("content")?,
+                updated_at: row.get("updated_at")?,
+            }),
+        ).optional()
+    }
+
+    // toutes les notes (sauvegarde)
+    pub fn get_all(conn: &Connection) -> Result<Vec<Note>> {
+        let mut stmt = conn.prepare("SELECT task_id, content, updated_at FROM notes ORDER BY task_id")?;
+        let notes = stmt.query_map([], |row| Ok(Note {
+            task_id: row.get("task_id")?,
+            content: row.get("content")?,
+            updated_at: row.get("updated_at")?,
+        }))?.collect::<Result<Vec<_>, _>>()?;
+        Ok(notes)
+    }
+
+    // enregistre (crée ou remplace) la note d'une tâche et retourne la note horodatée
+    pub fn save(task_id: u32, content: &str, conn: &Connection) -> Result<Note> {
+        let note = Note {
+            task_id,
+            content: content.to_string(),
+            updated_at: Local::now().naive_local(),
+        };
+        conn.execute(
+            "INSERT INTO notes (task_id, content, updated_at) VALUES (?1, ?2, ?3)
+            ON CONFLICT (task_id) DO UPDATE SET content = excluded.content, updated_at = excluded.updated_at;",
+            params![note.task_id, note.content, note.updated_at],
+        )?;
+        Ok(note)
+    }
+
+}
@@ -0,0 +1,101 @@
+use crate::models::task::Status;
+
+use rusqlite::{Connection, Result, Row, params};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+// Projets auxquels sont rattachées les tâches ; un projet archivé n'est plus proposé
+// dans les formulaires mais garde ses tâches
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub id: u32,
+    pub name: String,
+    pub color: String,
+    pub description: String,
+    pub archived: bool,
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            name: "".to_string(),
+            color: "#6c757d".to_string(),
+            description: "".to_string(),
+            archived: false,
+        }
+    }
+}
+
+const SQL_SELECT: &str = "SELECT id, name, color, description, archived FROM projects";
+
+impl Project {
+
+    fn from_row(row: &Row) -> Result<Project> {
+        Ok(Project {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            color: row.get("color")?,
+            description: row.get("description")?,
+            archived: row.get("archived")?,
+        })
+    }
+
+    // liste des projets par nom, les projets archivés seulement si demandé
+    pub fn get_all(include_archived: bool, conn: &Connection) -> Result<Vec<Project>> {
+        let mut stmt = conn.prepare(&format!("{SQL_SELECT} WHERE archived = 0 OR ?1 ORDER BY archived, name COLLATE NOCASE"))?;
+        let projects = stmt.query_map(params![include_archived], Project::from_row)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(projects)
+    }
+
+    // tous les projets (archivés compris) indexés par id, pour l'affichage des tâches
+    pub fn by_id(conn: &Connection) -> Result<HashMap<u32, Project>> {
+        Ok(Project::get_all(true, conn)?.into_iter().map(|project| (project.id, project)).collect())
+    }
+
+    pub fn get_by_id(id: u32, conn: &Connection) -> Result<Project> {
+        conn.query_row(&format!("{SQL_SELECT} WHERE id = ?1"), params![id], Project::from_row)
+    }
+
+    // vrai si un autre projet que celui donné porte déjà ce nom
+    pub fn name_taken(name: &str, id: Option<u32>, conn: &Connection) -> Result<bool> {
+        conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM projects WHERE name = ?1 AND id IS NOT ?2)",
+            params![name, id],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn insert(&mut self, conn: &Connection) -> Result<usize> {
+        let nb = conn.execute(
+            "INSERT INTO projects (name, color, description, archived) VALUES (?1, ?2, ?3, ?4);",
+            params![self.name, self.color, self.description, self.archived],
+        )?;
+        self.id = conn.last_insert_rowid() as u32;
+        Ok(nb)
+    }
+
+    pub fn update(&mut self, id: u32, conn: &Connection) -> Result<usize> {
+        self.id = id;
+        conn.execute(
+            "UPDATE projects SET name = ?1, color = ?2, description = ?3, archived = ?4 WHERE id = ?5;",
+            params![self.name, self.color, self.description, self.archived, id],
+        )
+    }
+
+    // nombre de tâches (hors corbeille) du projet pour chaque statut, dans l'ordre des statuts
+    pub fn status_counts(id: u32, conn: &Connection) -> Result<Vec<(Status, u32)>> {
+        let mut stmt = conn.prepare("SELECT status, COUNT(*) FROM tasks WHERE project_id = ?1 AND deleted_at IS NULL GROUP BY status")?;
+        let counts = stmt.query_map(params![id], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<Result<BTreeMap<Status, u32>, _>>()?;
+        Ok(Status::all().into_iter().map(|(status, _)| (status, counts.get(&status).copied().unwrap_or(0))).collect())
+    }
+
+    // nombre de tâches ouvertes (à faire, en cours ou bloquées) par projet
+    pub fn open_counts(conn: &Connection) -> Result<HashMap<u32, u32>> {
+        let mut stmt = conn.prepare("SELECT project_id, COUNT(*) FROM tasks WHERE project_id IS NOT NULL AND deleted_at IS NULL AND status IN (?1, ?2, ?3) GROUP BY project_id")?;
+        let counts = stmt.query_map(params![Status::ToDo, Status::InProgress, Status::Blocked], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(counts)
+    }
+
+}
@@ -0,0 +1,194 @@
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// Règle de répétition d'une tâche : quand une occurrence est terminée, la suivante est créée
+// avec pour échéance la prochaine date donnée par la règle.
+// Elle est enregistrée sous forme de texte : daily, weekly:mon,thu, monthly:15, after:10
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Recurrence {
+    // tous les jours
+    Daily,
+    // chaque semaine, les jours donnés
+    Weekly(Vec<Weekday>),
+    // chaque mois, le jour donné (le dernier jour pour les mois plus courts)
+    Monthly(u32),
+    // N jours après la fin de l'occurrence précédente
+    AfterCompletion(u32),
+}
+
+// nombre de jours maximum d'une répétition après la fin (dix ans)
+const MAX_AFTER_DAYS: u32 = 3650;
+
+const WEEKDAY_LABELS: [&str; 7] = ["lun", "mar", "mer", "jeu", "ven", "sam", "dim"];
+
+impl Recurrence {
+
+    // libellé affiché dans les vues
+    pub fn label(&self) -> String {
+        match self {
+            Recurrence::Daily => "Tous les jours".to_string(),
+            Recurrence::Weekly(days) => format!(
+                "Chaque semaine ({})",
+                days.iter().map(|day| WEEKDAY_LABELS[day.num_days_from_monday() as usize]).collect::<Vec<_>>().join(", ")
+            ),
+            Recurrence::Monthly(day) => format!("Chaque mois le {day}"),
+            Recurrence::AfterCompletion(1) => "1 jour après la fin".to_string(),
+            Recurrence::AfterCompletion(days) => format!("{days} jours après la fin"),
+        }
+    }
+
+    // échéance de l'occurrence suivante. Les règles de calendrier partent de l'échéance de
+    // l'occurrence terminée, ou du jour de fin si elle est terminée en retard.
+    // Au-delà de la dernière date représentable, l'échéance reste cette dernière date
+    pub fn next_date(&self, due_date: Option<NaiveDate>, completion_date: NaiveDate) -> NaiveDate {
+        let base = due_date.map_or(completion_date, |due_date| due_date.max(completion_date));
+        let after = |date: NaiveDate, days: u64| date.checked_add_days(Days::new(days)).unwrap_or(NaiveDate::MAX);
+        match self {
+            Recurrence::Daily => after(base, 1),
+            Recurrence::Weekly(days) => (1..=7)
+                .map(|n| after(base, n))
+                .find(|date| days.contains(&date.weekday()))
+                .unwrap_or(after(base, 7)),
+            Recurrence::Monthly(day) => {
+                let this_month = day_of_month(base, *day);
+                if this_month > base {
+                    this_month
+                } else {
+                    base.checked_add_months(Months::new(1)).map_or(NaiveDate::MAX, |date| day_of_month(date, *day))
+                }
+            }
+            Recurrence::AfterCompletion(days) => after(completion_date, (*days).into()),
+        }
+    }
+
+}
+
+// Le jour donné du mois de la date, ramené au dernier jour du mois s'il n'existe pas
+
+fn day_of_month(date: NaiveDate, day: u32) -> NaiveDate {
+    (1..=day)
+        .rev()
+        .find_map(|day| date.with_day(day))
+        .unwrap_or(date)
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly(days) => write!(
+                f,
+                "weekly:{}",
+                days.iter().map(|day| day.to_string().to_lowercase()).collect::<Vec<_>>().join(",")
+            ),
+            Recurrence::Monthly(day) => write!(f, "monthly:{day}"),
+            Recurrence::AfterCompletion(days) => write!(f, "after:{days}"),
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Récurrence invalide : {s}");
+        let (kind, value) = s.trim().split_once(':').unwrap_or((s.trim(), ""));
+        match kind {
+            "daily" => Ok(Recurrence::Daily),
+            "weekly" => {
+                let mut days = value
+                    .split(',')
+                    .filter(|day| !day.trim().is_empty())
+                    .map(|day| day.trim().parse::<Weekday>().map_err(|_| invalid()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                days.sort_by_key(|day| day.num_days_from_monday());
+                days.dedup();
+                if days.is_empty() {
+                    return Err(invalid());
+                }
+                Ok(Recurrence::Weekly(days))
+            }
+            "monthly" => match value.parse() {
+                Ok(day @ 1..=31) => Ok(Recurrence::Monthly(day)),
+                _ => Err(invalid()),
+            },
+            "after" => match value.parse() {
+                Ok(days @ 1..=MAX_AFTER_DAYS) => Ok(Recurrence::AfterCompletion(days)),
+                _ => Err(invalid()),
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(value: Recurrence) -> Self {
+        value.to_string()
+    }
+}
+
+impl FromSql for Recurrence {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_str()?.parse().map_err(|err: String| FromSqlError::Other(err.into()))
+    }
+}
+
+impl ToSql for Recurrence {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        for text in ["daily", "weekly:mon,thu", "monthly:31", "after:10"] {
+            assert_eq!(text.parse::<Recurrence>().unwrap().to_string(), text);
+        }
+        // les jours sont triés et dédoublonnés
+        assert_eq!("weekly:Sun, mon,sun".parse::<Recurrence>().unwrap().to_string(), "weekly:mon,sun");
+    }
+
+    #[test]
+    fn invalid() {
+        for text in ["", "hourly", "weekly:", "weekly:lundi", "monthly:0", "monthly:32", "after:0", "after:3651", "after:4294967295", "after:x"] {
+            assert!(text.parse::<Recurrence>().is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn next_date() {
+        let weekly = Recurrence::Weekly(vec![Weekday::Mon, Weekday::Thu]);
+        // jeudi 2026-10-15 -> lundi 2026-10-19
+        assert_eq!(weekly.next_date(Some(date("2026-10-15")), date("2026-10-14")), date("2026-10-19"));
+        // terminée en retard : on part du jour de fin
+        assert_eq!(Recurrence::Daily.next_date(Some(date("2026-10-01")), date("2026-10-05")), date("2026-10-06"));
+        assert_eq!(Recurrence::Monthly(31).next_date(Some(date("2027-01-31")), date("2027-01-30")), date("2027-02-28"));
+        assert_eq!(Recurrence::AfterCompletion(3).next_date(Some(date("2026-12-01")), date("2026-10-05")), date("2026-10-08"));
+        // pas de débordement près de la dernière date représentable
+        assert_eq!(Recurrence::AfterCompletion(MAX_AFTER_DAYS).next_date(None, NaiveDate::MAX - Days::new(1)), NaiveDate::MAX);
+        assert_eq!(Recurrence::Daily.next_date(Some(NaiveDate::MAX), NaiveDate::MAX), NaiveDate::MAX);
+        assert_eq!(Recurrence::Monthly(1).next_date(None, NaiveDate::MAX), NaiveDate::MAX);
+        assert_eq!(Recurrence::Weekly(vec![Weekday::Mon]).next_date(None, NaiveDate::MAX), NaiveDate::MAX);
+    }
+}
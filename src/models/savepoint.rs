@@ -0,0 +1,43 @@
+use rusqlite::{Connection, Result};
+use std::ops::Deref;
+
+// Transaction imbriquable (SAVEPOINT de sqlite) : hors transaction elle se comporte comme
+// une transaction, dans une transaction en cours comme une sous-transaction. Toutes les
+// transactions des modèles l'utilisent, ce qui permet d'enchaîner des opérations qui ont
+// chacune leur transaction dans une seule (import, restauration).
+// Sans commit, les modifications sont annulées à la fin du bloc
+
+pub struct Savepoint<'a> {
+    conn: &'a Connection,
+    released: bool,
+}
+
+impl<'a> Savepoint<'a> {
+
+    pub fn new(conn: &'a Connection) -> Result<Savepoint<'a>> {
+        conn.execute_batch("SAVEPOINT nested")?;
+        Ok(Savepoint { conn, released: false })
+    }
+
+    pub fn commit(mut self) -> Result<()> {
+        self.released = true;
+        self.conn.execute_batch("RELEASE nested")
+    }
+
+}
+
+impl Deref for Savepoint<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn
+    }
+}
+
+impl Drop for Savepoint<'_> {
+    fn drop(&mut self) {
+        if !self.released {
+            let _ = self.conn.execute_batch("ROLLBACK TO nested; RELEASE nested");
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use crate::enum_with_strings;
+use crate::models::savepoint::Savepoint;
+use crate::models::task::{Duration, Importance, Priority, Status, Task};
+
+use chrono::Local;
+use rusqlite::{Connection, Result, params};
+use rusqlite::types::{FromSql, FromSqlError, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+
+// Profil de calcul du score des tâches : chaque critère rapporte des points, la somme
+// est ensuite multipliée par le coefficient du statut.
+// Les valeurs par défaut sont dans le code, la table scoring_weights ne garde que les
+// valeurs modifiées depuis la page de paramètres.
+
+enum_with_strings!(Factor {
+    Priority => "Priorité",
+    Importance => "Importance",
+    Duration => "Durée",
+    Due => "Échéance",
+    Age => "Ancienneté",
+    Dependents => "Tâches en attente",
+    Status => "Coefficient du statut",
+});
+
+// Paliers d'échéance : (clé, libellé, points par défaut)
+
+const DUE_STEPS: [(&str, &str, i64); 5] = [
+    ("Overdue", "En retard", 6),
+    ("Today", "Aujourd'hui", 5),
+    ("TwoDays", "Sous 2 jours", 4),
+    ("Week", "Sous 7 jours", 2),
+    ("TwoWeeks", "Sous 14 jours", 1),
+];
+
+// Points maximum d'un poids, en valeur absolue : le score d'une tâche reste loin des
+// limites des entiers même avec de nombreuses semaines d'ancienneté ou tâches en attente
+
+pub const MAX_POINTS: i64 = 1000;
+
+// Un poids du profil : le critère, la valeur concernée (ex : Urgent) et ses points
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Weight {
+    pub factor: Factor,
+    pub variant: String,
+    pub label: String,
+    pub points: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoringProfile {
+    pub weights: Vec<Weight>,
+}
+
+// Détail du score d'une tâche, affiché sur la page d'édition
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoreLine {
+    pub factor: Factor,
+    pub label: String,
+    pub points: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoreBreakdown {
+    pub lines: Vec<ScoreLine>,
+    pub subtotal: i64,
+    pub multiplier: i64,
+    pub total: i64,
+}
+
+// Clé enregistrée pour une valeur d'enum : le nom de la variante (ex : VeryUrgent)
+
+fn variant_key(value: impl std::fmt::Debug) -> String {
+    format!("{value:?}")
+}
+
+fn weight(factor: Factor, variant: String, label: &str, points: i64) -> Weight {
+    Weight {
+        factor,
+        variant,
+        label: label.to_string(),
+        points,
+    }
+}
+
+impl Default for ScoringProfile {
+    // les poids historiques de Task::update_scoring
+    fn default() -> Self {
+        let mut weights = vec![];
+        for (priority, label) in Priority::all() {
+            let points = match priority {
+                Priority::ToBeDefined => 0,
+                Priority::NotUrgent => 1,
+                Priority::Normal => 2,
+                Priority::Urgent => 3,
+                Priority::VeryUrgent => 4,
+            };
+            weights.push(weight(Factor::Priority, variant_key(priority), &label, points));
+        }
+        for (importance, label) in Importance::all() {
+            let points = match importance {
+                Importance::ToBeDefined => 0,
+                Importance::NotImportant => 1,
+                Importance::Normal => 2,
+                Importance::Important => 3,
+                Importance::VeryImportant => 4,
+            };
+            weights.push(weight(Factor::Importance, variant_key(importance), &label, points));
+        }
+        for (duration, label) in Duration::all() {
+            let points = match duration {
+                Duration::ToBeDefined => 0,
+                Duration::VeryLong => 1,
+                Duration::Long => 2,
+                Duration::Normal => 3,
+                Duration::Short => 4,
+                Duration::VeryShort => 5,
+            };
+            weights.push(weight(Factor::Duration, variant_key(duration), &label, points));
+        }
+        for (key, label, points) in DUE_STEPS {
+            weights.push(weight(Factor::Due, key.to_string(), label, points));
+        }
+        weights.push(weight(Factor::Age, "PerWeek".to_string(), "Par semaine depuis la création", 0));
+        weights.push(weight(Factor::Dependents, "PerTask".to_string(), "Par tâche qui l'attend", 0));
+        for (status, label) in Status::all() {
+            let points = match status {
+                Status::Canceled => 0,
+                Status::Finished => 1,
+                Status::Blocked => 2,
+                Status::ToDo => 3,
+                Status::InProgress => 4,
+            };
+            weights.push(weight(Factor::Status, variant_key(status), &label, points));
+        }
+        Self { weights }
+    }
+}
+
+impl ScoringProfile {
+
+    // profil en vigueur : les valeurs par défaut remplacées par celles enregistrées
+    pub fn load(conn: &Connection) -> Result<ScoringProfile> {
+        let mut profile = ScoringProfile::default();
+        let mut stmt = conn.prepare("SELECT factor, variant, points FROM scoring_weights")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, Factor>(0)?, row.get::<_, String>(1)?, row.get(2)?)))?;
+        for row in rows {
+            let (factor, variant, points) = row?;
+            if let Some(weight) = profile.weights.iter_mut().find(|w| w.factor == factor && w.variant == variant) {
+                weight.points = points;
+            }
+        }
+        Ok(profile)
+    }
+
+    // enregistre les poids qui diffèrent des valeurs par défaut
+    pub fn save(&self, conn: &Connection) -> Result<()> {
+        let defaults = ScoringProfile::default();
+        let tx = Savepoint::new(conn)?;
+        tx.execute("DELETE FROM scoring_weights;", [])?;
+        for weight in &self.weights {
+            if defaults.points(weight.factor, &weight.variant) != weight.points {
+                tx.execute(
+                    "INSERT INTO scoring_weights (factor, variant, points) VALUES (?1, ?2, ?3);",
+                    params![weight.factor, weight.variant, weight.points],
+                )?;
+            }
+        }
+        tx.commit()
+    }
+
+    // vérifie les points saisis pour un poids
+    pub fn valid_points(points: i64) -> bool {
+        (-MAX_POINTS..=MAX_POINTS).contains(&points)
+    }
+
+    pub fn points(&self, factor: Factor, variant: &str) -> i64 {
+        self.weights
+            .iter()
+            .find(|weight| weight.factor == factor && weight.variant == variant)
+            .map_or(0, |weight| weight.points)
+    }
+
+    // ligne du détail pour un poids appliqué count fois
+    fn line(&self, factor: Factor, variant: &str, count: i64) -> Option<ScoreLine> {
+        let found = self.weights.iter().find(|w| w.factor == factor && w.variant == variant)?;
+        Some(ScoreLine {
+            factor,
+            label: if count == 1 { found.label.clone() } else { format!("{} × {count}", found.label) },
+            points: found.points.saturating_mul(count),
+        })
+    }
+
+    // détail du score d'une tâche ; waiting est le nombre de tâches ouvertes qui l'attendent
+    pub fn breakdown(&self, task: &Task, waiting: i64) -> ScoreBreakdown {
+        let today = Local::now().date_naive();
+        let due_step = match task.due_date.map(|date| (date - today).num_days()) {
+            _ if task.is_overdue() => Some("Overdue"),
+            Some(0) => Some("Today"),
+            Some(1..=2) => Some("TwoDays"),
+            Some(3..=7) => Some("Week"),
+            Some(8..=14) => Some("TwoWeeks"),
+            _ => None,
+        };
+        let weeks = (today - task.creation_date).num_days().max(0) / 7;
+        let lines: Vec<ScoreLine> = [
+            self.line(Factor::Priority, &variant_key(task.priority), 1),
+            self.line(Factor::Importance, &variant_key(task.importance), 1),
+            self.line(Factor::Duration, &variant_key(task.duration), 1),
+            due_step.and_then(|step| self.line(Factor::Due, step, 1)),
+            (weeks > 0).then(|| self.line(Factor::Age, "PerWeek", weeks)).flatten(),
+            (waiting > 0).then(|| self.line(Factor::Dependents, "PerTask", waiting)).flatten(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let subtotal = lines.iter().fold(0, |subtotal: i64, line| subtotal.saturating_add(line.points));
+        let multiplier = self.points(Factor::Status, &variant_key(task.status));
+        ScoreBreakdown {
+            lines,
+            subtotal,
+            multiplier,
+            total: subtotal.saturating_mul(multiplier),
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::migration::test_database;
+    use chrono::Days;
+
+    fn task() -> Task {
+        Task {
+            priority: Priority::Urgent,
+            importance: Importance::VeryImportant,
+            duration: Duration::Short,
+            status: Status::InProgress,
+            creation_date: Local::now().date_naive() - Days::new(15),
+            due_date: Some(Local::now().date_naive() + Days::new(1)),
+            ..Task::default()
+        }
+    }
+
+    fn set(profile: &mut ScoringProfile, factor: Factor, variant: &str, points: i64) {
+        profile.weights.iter_mut().find(|w| w.factor == factor && w.variant == variant).unwrap().points = points;
+    }
+
+    #[test]
+    fn default_breakdown() {
+        let breakdown = ScoringProfile::default().breakdown(&task(), 0);
+        let points: Vec<(Factor, i64)> = breakdown.lines.iter().map(|line| (line.factor, line.points)).collect();
+        // l'ancienneté et les tâches en attente valent 0 par défaut
+        assert_eq!(points, vec![(Factor::Priority, 3), (Factor::Importance, 4), (Factor::Duration, 4), (Factor::Due, 4), (Factor::Age, 0)]);
+        assert_eq!((breakdown.subtotal, breakdown.multiplier, breakdown.total), (15, 4, 60));
+    }
+
+    #[test]
+    fn weighted_breakdown() {
+        let mut profile = ScoringProfile::default();
+        set(&mut profile, Factor::Age, "PerWeek", 2);
+        set(&mut profile, Factor::Dependents, "PerTask", 5);
+        let breakdown = profile.breakdown(&task(), 3);
+        let age = breakdown.lines.iter().find(|line| line.factor == Factor::Age).unwrap();
+        assert_eq!((age.label.as_str(), age.points), ("Par semaine depuis la création × 2", 4));
+        let dependents = breakdown.lines.iter().find(|line| line.factor == Factor::Dependents).unwrap();
+        assert_eq!(dependents.points, 15);
+        assert_eq!(breakdown.total, (15 + 4 + 15) * 4);
+    }
+
+    // des poids enregistrés avant la limite ne font pas déborder le calcul
+    #[test]
+    fn breakdown_saturates() {
+        let mut profile = ScoringProfile::default();
+        set(&mut profile, Factor::Priority, "Urgent", i64::MAX);
+        set(&mut profile, Factor::Dependents, "PerTask", i64::MAX);
+        set(&mut profile, Factor::Status, "InProgress", i64::MAX);
+        assert_eq!(profile.breakdown(&task(), 2).total, i64::MAX);
+        set(&mut profile, Factor::Status, "InProgress", -2);
+        assert_eq!(profile.breakdown(&task(), 2).total, i64::MIN);
+        assert!(ScoringProfile::valid_points(-MAX_POINTS) && ScoringProfile::valid_points(MAX_POINTS));
+        assert!(!ScoringProfile::valid_points(MAX_POINTS + 1));
+    }
+
+    #[test]
+    fn save_and_load() {
+        let conn = test_database();
+        let mut profile = ScoringProfile::default();
+        set(&mut profile, Factor::Due, "Today", 9);
+        profile.save(&conn).unwrap();
+        // seuls les poids modifiés sont enregistrés
+        let stored: i64 = conn.query_row("SELECT COUNT(*) FROM scoring_weights", [], |row| row.get(0)).unwrap();
+        assert_eq!(stored, 1);
+        assert_eq!(ScoringProfile::load(&conn).unwrap().points(Factor::Due, "Today"), 9);
+        ScoringProfile::default().save(&conn).unwrap();
+        assert_eq!(ScoringProfile::load(&conn).unwrap().points(Factor::Due, "Today"), 5);
+    }
+}
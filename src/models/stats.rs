@@ -0,0 +1,275 @@
+use crate::models::project::Project;
+use crate::models::task::{Duration, Filter, Priority, Status, Task};
+
+use chrono::{Datelike, Days, Local, NaiveDate, NaiveDateTime};
+use rusqlite::{Connection, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+// Statistiques sur une période : tâches terminées par jour et par semaine, délais moyens
+// et flux cumulé des statuts, reconstitué à partir de l'historique des changements de statut
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Stats {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    // nombre de tâches terminées chaque jour de la période
+    pub daily: Vec<(NaiveDate, u32)>,
+    // nombre de tâches terminées par semaine (date du lundi)
+    pub weekly: Vec<(NaiveDate, u32)>,
+    pub by_priority: Vec<LeadTime>,
+    pub by_duration: Vec<LeadTime>,
+    pub by_project: Vec<LeadTime>,
+    // jours de la période et, pour chaque statut, le nombre de tâches dans ce statut ce jour-là
+    pub flow_dates: Vec<NaiveDate>,
+    pub flow: Vec<FlowSeries>,
+}
+
+// Délais moyens en jours des tâches terminées d'un groupe : création → fin (lead time)
+// et début → fin (cycle time, seulement pour les tâches qui ont une date de début)
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LeadTime {
+    pub label: String,
+    pub count: u32,
+    pub lead_time: Option<f64>,
+    pub cycle_time: Option<f64>,
+}
+
+// Ordre d'empilement du flux cumulé, de bas en haut
+
+const FLOW_ORDER: [Status; 5] = [Status::Finished, Status::Canceled, Status::Blocked, Status::InProgress, Status::ToDo];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FlowSeries {
+    pub status: Status,
+    pub label: String,
+    pub counts: Vec<u32>,
+}
+
+// Période par défaut (les 30 derniers jours) et durée maximale d'une période
+
+const DEFAULT_DAYS: u64 = 30;
+const MAX_DAYS: i64 = 366;
+
+// période demandée, complétée par défaut ; None si elle est inversée ou trop longue
+pub fn period(from: Option<NaiveDate>, to: Option<NaiveDate>) -> Option<(NaiveDate, NaiveDate)> {
+    let to = to.unwrap_or_else(|| Local::now().date_naive());
+    let from = from.unwrap_or(to - Days::new(DEFAULT_DAYS - 1));
+    (from <= to && (to - from).num_days() < MAX_DAYS).then_some((from, to))
+}
+
+fn days(from: NaiveDate, to: NaiveDate) -> impl Iterator<Item = NaiveDate> {
+    from.iter_days().take_while(move |day| *day <= to)
+}
+
+fn average(values: impl Iterator<Item = i64>) -> Option<f64> {
+    let (sum, count) = values.fold((0, 0), |(sum, count), value| (sum + value, count + 1));
+    (count > 0).then(|| (sum as f64 / count as f64 * 10.0).round() / 10.0)
+}
+
+fn lead_time<'a>(label: String, tasks: impl Iterator<Item = &'a Task> + Clone) -> LeadTime {
+    let completion = |task: &Task| task.completion_date.unwrap_or(task.creation_date);
+    LeadTime {
+        label,
+        count: tasks.clone().count() as u32,
+        lead_time: average(tasks.clone().map(|task| (completion(task) - task.creation_date).num_days())),
+        cycle_time: average(tasks.filter_map(|task| Some((completion(task) - task.start_date?).num_days()))),
+    }
+}
+
+// Changement de statut d'une tâche : (jour, ancien statut, nouveau statut)
+
+type StatusChange = (NaiveDate, Option<Status>, Option<Status>);
+
+// Changements de statut de chaque tâche, dans l'ordre
+
+fn status_changes(conn: &Connection) -> Result<HashMap<u32, Vec<StatusChange>>> {
+    let mut stmt = conn.prepare("SELECT task_id, created_at, old_status, new_status FROM task_events WHERE new_status IS NOT NULL ORDER BY created_at, id")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, u32>(0)?, row.get::<_, NaiveDateTime>(1)?, row.get::<_, Option<Status>>(2)?, row.get::<_, Option<Status>>(3)?))
+    })?;
+    let mut changes: HashMap<u32, Vec<_>> = HashMap::new();
+    for row in rows {
+        let (task_id, created_at, old_status, new_status) = row?;
+        changes.entry(task_id).or_default().push((created_at.date(), old_status, new_status));
+    }
+    Ok(changes)
+}
+
+impl Stats {
+
+    // statistiques des tâches hors corbeille entre from et to (inclus)
+    pub fn compute(from: NaiveDate, to: NaiveDate, conn: &Connection) -> Result<Stats> {
+        let tasks = Task::get_with_filter(conn, &Filter::All)?;
+        let completed: Vec<&Task> = tasks
+            .iter()
+            .filter(|task| task.status == Status::Finished && task.completion_date.is_some_and(|date| date >= from && date <= to))
+            .collect();
+
+        let daily: Vec<(NaiveDate, u32)> = days(from, to)
+            .map(|day| (day, completed.iter().filter(|task| task.completion_date == Some(day)).count() as u32))
+            .collect();
+        let monday = |day: NaiveDate| day - Days::new(day.weekday().num_days_from_monday().into());
+        let mut weekly: Vec<(NaiveDate, u32)> = vec![];
+        for (day, count) in &daily {
+            match weekly.last_mut() {
+                Some((week, total)) if *week == monday(*day) => *total += count,
+                _ => weekly.push((monday(*day), *count)),
+            }
+        }
+
+        let by_priority = Priority::all()
+            .into_iter()
+            .map(|(priority, label)| lead_time(label, completed.iter().copied().filter(move |task| task.priority == priority)))
+            .collect();
+        let by_duration = Duration::all()
+            .into_iter()
+            .map(|(duration, label)| lead_time(label, completed.iter().copied().filter(move |task| task.duration == duration)))
+            .collect();
+        let mut by_project: Vec<LeadTime> = Project::get_all(true, conn)?
+            .into_iter()
+            .map(|project| lead_time(project.name, completed.iter().copied().filter(move |task| task.project_id == Some(project.id))))
+            .filter(|lead_time| lead_time.count > 0)
+            .collect();
+        by_project.push(lead_time("Sans projet".to_string(), completed.iter().copied().filter(|task| task.project_id.is_none())));
+
+        // statut de chaque tâche à la fin de chaque jour : celui du dernier changement passé,
+        // sinon l'ancien statut du premier changement, sinon le statut actuel
+        let changes = status_changes(conn)?;
+        let flow_dates: Vec<NaiveDate> = days(from, to).collect();
+        let mut counts: BTreeMap<Status, Vec<u32>> = BTreeMap::new();
+        for task in &tasks {
+            let task_changes = changes.get(&task.id).map(Vec::as_slice).unwrap_or_default();
+            for (i, day) in flow_dates.iter().enumerate() {
+                if task.creation_date > *day {
+                    continue;
+                }
+                let status = match task_changes.iter().rev().find(|(date, _, _)| date <= day) {
+                    Some((_, _, new_status)) => *new_status,
+                    None => task_changes.first().map_or(Some(task.status), |(_, old_status, _)| *old_status),
+                };
+                if let Some(status) = status {
+                    counts.entry(status).or_insert_with(|| vec![0; flow_dates.len()])[i] += 1;
+                }
+            }
+        }
+        let flow = FLOW_ORDER
+            .into_iter()
+            .map(|status| FlowSeries {
+                status,
+                label: status.to_string(),
+                counts: counts.remove(&status).unwrap_or_else(|| vec![0; flow_dates.len()]),
+            })
+            .collect();
+
+        Ok(Stats {
+            from,
+            to,
+            daily,
+            weekly,
+            by_priority,
+            by_duration,
+            by_project,
+            flow_dates,
+            flow,
+        })
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::history::{EventKind, HistoryEntry};
+    use crate::models::migration::test_database;
+    use rusqlite::params;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    // tâche créée à la date donnée, passée directement au statut donné
+    fn add(description: &str, created: &str, status: Status, dates: (Option<&str>, Option<&str>), conn: &Connection) -> Task {
+        let mut task = Task { description: description.to_string(), creation_date: date(created), priority: Priority::Urgent, ..Task::default() };
+        task.insert(conn).unwrap();
+        conn.execute("UPDATE tasks SET status = ?1 WHERE id = ?2", params![status, task.id]).unwrap();
+        Task::set_dates(task.id, dates.0.map(date), dates.1.map(date), conn).unwrap();
+        task
+    }
+
+    fn change(task: &Task, day: &str, old_status: Status, new_status: Status, conn: &Connection) {
+        HistoryEntry {
+            id: 0,
+            task_id: task.id,
+            kind: EventKind::StatusChanged,
+            field: Some("Statut".to_string()),
+            old_value: Some(old_status.to_string()),
+            new_value: Some(new_status.to_string()),
+            old_status: Some(old_status),
+            new_status: Some(new_status),
+            created_at: date(day).and_hms_opt(12, 0, 0).unwrap(),
+        }
+        .insert(task.id, conn)
+        .unwrap();
+    }
+
+    fn flow(stats: &Stats, status: Status) -> Vec<u32> {
+        stats.flow.iter().find(|series| series.status == status).unwrap().counts.clone()
+    }
+
+    #[test]
+    fn period_bounds() {
+        let today = Local::now().date_naive();
+        assert_eq!(period(None, None), Some((today - Days::new(29), today)));
+        assert_eq!(period(Some(date("2026-03-01")), Some(date("2026-03-01"))), Some((date("2026-03-01"), date("2026-03-01"))));
+        assert_eq!(period(Some(date("2026-03-02")), Some(date("2026-03-01"))), None);
+        // au plus 366 jours, bornes comprises
+        assert!(period(Some(date("2025-01-01")), Some(date("2026-01-01"))).is_some());
+        assert_eq!(period(Some(date("2025-01-01")), Some(date("2026-01-02"))), None);
+    }
+
+    #[test]
+    fn throughput_and_lead_times() {
+        let conn = test_database();
+        add("Fini lundi", "2026-02-26", Status::Finished, (Some("2026-03-01"), Some("2026-03-02")), &conn);
+        add("Fini mercredi", "2026-03-02", Status::Finished, (None, Some("2026-03-04")), &conn);
+        add("Fini dimanche", "2026-03-06", Status::Finished, (Some("2026-03-07"), Some("2026-03-08")), &conn);
+        add("Hors période", "2026-03-01", Status::Finished, (None, Some("2026-03-20")), &conn);
+        add("Annulé", "2026-03-01", Status::Canceled, (None, Some("2026-03-03")), &conn);
+        let trashed = add("Corbeille", "2026-03-01", Status::Finished, (None, Some("2026-03-03")), &conn);
+        Task::delete(trashed.id, &conn).unwrap();
+
+        // du samedi 28/02 au mardi 10/03 : trois semaines entamées
+        let stats = Stats::compute(date("2026-02-28"), date("2026-03-10"), &conn).unwrap();
+        let finished: Vec<(NaiveDate, u32)> = stats.daily.iter().copied().filter(|(_, count)| *count > 0).collect();
+        assert_eq!(finished, vec![(date("2026-03-02"), 1), (date("2026-03-04"), 1), (date("2026-03-08"), 1)]);
+        assert_eq!(stats.daily.len(), 11);
+        assert_eq!(stats.weekly, vec![(date("2026-02-23"), 0), (date("2026-03-02"), 3), (date("2026-03-09"), 0)]);
+
+        let urgent = stats.by_priority.iter().find(|lead_time| lead_time.label == Priority::Urgent.to_string()).unwrap();
+        // délais 4, 2 et 2 jours ; début → fin 1 et 1 jour
+        assert_eq!((urgent.count, urgent.lead_time, urgent.cycle_time), (3, Some(2.7), Some(1.0)));
+        let without_project = stats.by_project.last().unwrap();
+        assert_eq!((without_project.label.as_str(), without_project.count), ("Sans projet", 3));
+    }
+
+    // le statut de chaque jour vient de l'historique des changements, le statut actuel
+    // ne sert qu'aux tâches sans changement
+    #[test]
+    fn cumulative_flow() {
+        let conn = test_database();
+        let task = add("Suivie", "2026-03-01", Status::Finished, (Some("2026-03-02"), Some("2026-03-04")), &conn);
+        change(&task, "2026-03-02", Status::ToDo, Status::InProgress, &conn);
+        change(&task, "2026-03-04", Status::InProgress, Status::Finished, &conn);
+        add("Sans changement", "2026-03-03", Status::Blocked, (None, None), &conn);
+
+        let stats = Stats::compute(date("2026-03-01"), date("2026-03-05"), &conn).unwrap();
+        assert_eq!(stats.flow.iter().map(|series| series.status).collect::<Vec<_>>(), FLOW_ORDER.to_vec());
+        assert_eq!(flow(&stats, Status::ToDo), vec![1, 0, 0, 0, 0]);
+        assert_eq!(flow(&stats, Status::InProgress), vec![0, 1, 1, 0, 0]);
+        assert_eq!(flow(&stats, Status::Finished), vec![0, 0, 0, 1, 1]);
+        assert_eq!(flow(&stats, Status::Blocked), vec![0, 0, 1, 1, 1]);
+        assert_eq!(flow(&stats, Status::Canceled), vec![0; 5]);
+    }
+}
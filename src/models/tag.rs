@@ -0,0 +1,85 @@
+use crate::models::history::HistoryEntry;
+use crate::models::savepoint::Savepoint;
+
+use rusqlite::{Connection, Result, params};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Étiquettes libres posées sur les tâches (plusieurs par tâche, table de liaison task_tags)
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: u32,
+    pub name: String,
+}
+
+impl Tag {
+
+    // toutes les étiquettes utilisées, par nom
+    pub fn get_all(conn: &Connection) -> Result<Vec<Tag>> {
+        let mut stmt = conn.prepare("SELECT id, name FROM tags ORDER BY name COLLATE NOCASE")?;
+        let tags = stmt.query_map([], |row| Ok(Tag { id: row.get(0)?, name: row.get(1)? }))?.collect::<Result<Vec<_>, _>>()?;
+        Ok(tags)
+    }
+
+    // noms des étiquettes d'une tâche
+    pub fn for_task(task_id: u32, conn: &Connection) -> Result<Vec<String>> {
+        let mut stmt = conn.prepare("SELECT tags.name FROM task_tags JOIN tags ON tags.id = task_tags.tag_id WHERE task_tags.task_id = ?1 ORDER BY tags.name COLLATE NOCASE")?;
+        let names = stmt.query_map(params![task_id], |row| row.get(0))?.collect::<Result<Vec<_>, _>>()?;
+        Ok(names)
+    }
+
+    // noms des étiquettes de chaque tâche, pour l'affichage des listes
+    pub fn by_task(conn: &Connection) -> Result<HashMap<u32, Vec<String>>> {
+        let mut stmt = conn.prepare("SELECT task_tags.task_id, tags.name FROM task_tags JOIN tags ON tags.id = task_tags.tag_id ORDER BY tags.name COLLATE NOCASE")?;
+        let mut tags: HashMap<u32, Vec<String>> = HashMap::new();
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        for row in rows {
+            let (task_id, name) = row?;
+            tags.entry(task_id).or_default().push(name);
+        }
+        Ok(tags)
+    }
+
+    // remplace les étiquettes d'une tâche : les étiquettes inconnues sont créées et celles
+    // qui ne sont plus utilisées par aucune tâche sont supprimées
+    pub fn set_for_task(task_id: u32, names: &[String], conn: &Connection) -> Result<()> {
+        let old = Tag::for_task(task_id, conn)?;
+        let tx = Savepoint::new(conn)?;
+        tx.execute("DELETE FROM task_tags WHERE task_id = ?1;", params![task_id])?;
+        for name in names {
+            tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1);", params![name])?;
+            tx.execute(
+                "INSERT OR IGNORE INTO task_tags (task_id, tag_id) SELECT ?1, id FROM tags WHERE name = ?2;",
+                params![task_id, name],
+            )?;
+        }
+        tx.execute("DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM task_tags);", [])?;
+        let new = Tag::for_task(task_id, &tx)?;
+        if old != new {
+            HistoryEntry::record_tags(&tx, task_id, &old, &new)?;
+        }
+        tx.commit()
+    }
+
+}
+
+// Découpe la saisie « réunion, client X » en noms d'étiquettes (sans doublons, casse ignorée)
+
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for name in input.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        if !names.iter().any(|known| known.to_lowercase() == name.to_lowercase()) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+// Vrai si la tâche porte toutes les étiquettes demandées (casse ignorée)
+
+pub fn has_all_tags(task_tags: Option<&Vec<String>>, wanted: &[String]) -> bool {
+    wanted.iter().all(|name| {
+        task_tags.is_some_and(|tags| tags.iter().any(|tag| tag.to_lowercase() == name.to_lowercase()))
+    })
+}
@@ -1,9 +1,16 @@
 use crate::enum_with_strings;
+use crate::models::history::{EventKind, HistoryEntry};
+use crate::models::recurrence::Recurrence;
+use crate::models::savepoint::Savepoint;
+use crate::models::scoring::ScoringProfile;
+use crate::models::tag::Tag;
+use crate::models::time_entry::TimeEntry;
 
-use chrono::{NaiveDate, Local};
-use rusqlite::{Connection, Result, params};
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use rusqlite::{Connection, Result, Row, params};
 use rusqlite::types::{FromSql, FromSqlError, ToSql, ToSqlOutput, ValueRef};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 enum_with_strings!(Priority {
     ToBeDefined => "A définir",
@@ -49,8 +56,18 @@ pub struct Task {
     pub completion_date: Option<NaiveDate>,
     pub start_date: Option<NaiveDate>,
     pub status: Status,
+    // nom du projet, lu dans la table projects (seul project_id est enregistré)
     pub grouping: String,
-    pub scoring: u8
+    pub scoring: i64,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub parent_id: Option<u32>,
+    pub project_id: Option<u32>,
+    pub due_date: Option<NaiveDate>,
+    // heure limite facultative, sinon la tâche est due en fin de journée
+    pub due_time: Option<NaiveTime>,
+    pub recurrence: Option<Recurrence>,
+    // occurrence précédente d'une tâche récurrente
+    pub previous_id: Option<u32>,
 }
 
 enum_with_strings!(Filter {
@@ -60,7 +77,10 @@ enum_with_strings!(Filter {
     Blocked => "Tâches bloquées", 
     Quick => "Taches rapide", 
     UnClassified => "Tâches non classées",
-    ByProject => "Tâches par projets" 
+    ByProject => "Tâches par projets",
+    Overdue => "Tâches en retard",
+    DueToday => "Tâches pour aujourd'hui",
+    DueThisWeek => "Tâches pour cette semaine",
 });
 
 impl Default for Task {
@@ -76,132 +96,723 @@ impl Default for Task {
             start_date: None,
             status: Status::ToDo,
             scoring:0,
-            grouping:"".to_string()
+            grouping:"".to_string(),
+            deleted_at: None,
+            parent_id: None,
+            project_id: None,
+            due_date: None,
+            due_time: None,
+            recurrence: None,
+            previous_id: None,
         }
     }
 }
 
+// Colonnes lues par Task::from_row, le nom du projet vient de la jointure SQL_TASKS
+
+const SQL_COLUMNS: &str = "tasks.id, tasks.description, priority, importance, duration, creation_date, completion_date, start_date, status, coalesce(projects.name, '') AS grouping, scoring, deleted_at, parent_id, project_id, due_date, due_time, recurrence, previous_id";
+const SQL_TASKS: &str = "tasks LEFT JOIN projects ON projects.id = tasks.project_id";
+
+// Tâches encore ouvertes (statuts passés en ?1, ?2 et ?3 : à faire, en cours, bloquée)
+
+const SQL_OPEN: &str = "status IN (?1, ?2, ?3)";
+
+// Marqueurs entourant les termes trouvés par la recherche (remplacés à l'affichage)
+
+pub const MATCH_START: &str = "\u{2}";
+pub const MATCH_END: &str = "\u{3}";
+
+// Résultat de recherche : la tâche et ses champs avec les termes trouvés marqués
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub task: Task,
+    pub description: String,
+    pub grouping: String,
+    pub notes: String,
+}
+
+// Moment où une tâche est due : l'heure limite ou, à défaut, la fin de la journée
+
+pub fn deadline(due_date: NaiveDate, due_time: Option<NaiveTime>) -> NaiveDateTime {
+    due_date.and_time(due_time.unwrap_or(NaiveTime::from_hms_opt(23, 59, 59).unwrap()))
+}
+
+impl Status {
+    // une tâche terminée ou annulée ne bloque plus les tâches qui en dépendent
+    pub fn is_resolved(&self) -> bool {
+        matches!(self, Status::Finished | Status::Canceled)
+    }
+}
+
 impl Task {
 
+    // construit une tâche à partir d'une ligne sélectionnée avec SQL_COLUMNS
+    fn from_row(row: &Row) -> Result<Task> {
+        Ok(Task {
+            id: row.get("id")?,
+            description: row.get("description")?,
+            priority: row.get("priority")?,
+            importance: row.get("importance")?,
+            duration: row.get("duration")?,
+            creation_date: row.get("creation_date")?,
+            completion_date: row.get("completion_date")?,
+            start_date: row.get("start_date")?,
+            status: row.get("status")?,
+            grouping: row.get("grouping")?,
+            scoring: row.get("scoring")?,
+            deleted_at: row.get("deleted_at")?,
+            parent_id: row.get("parent_id")?,
+            project_id: row.get("project_id")?,
+            due_date: row.get("due_date")?,
+            due_time: row.get("due_time")?,
+            recurrence: row.get("recurrence")?,
+            previous_id: row.get("previous_id")?,
+        })
+    }
+
     pub fn update_date(&mut self){
        (self.completion_date,self.start_date) = match self.status {
-            Status::Finished => (Some(Local::now().date_naive()),if self.start_date==None {Some(Local::now().date_naive())} else {self.start_date}),
+            Status::Finished => (Some(Local::now().date_naive()),if self.start_date.is_none() {Some(Local::now().date_naive())} else {self.start_date}),
             Status::InProgress => (None,Some(Local::now().date_naive())),
             _ => (self.completion_date,self.start_date)
         }
     }
 
-    pub fn update_scoring(&mut self){
-        self.scoring=0;
-
-        self.scoring += match self.priority {
-            Priority::ToBeDefined => 0,
-            Priority::NotUrgent => 1,
-            Priority::Normal => 2,
-            Priority::Urgent => 3,
-            Priority::VeryUrgent => 4,
-        };
-
-        self.scoring += match self.importance {
-            Importance::ToBeDefined =>0,
-            Importance::NotImportant => 1,
-            Importance::Normal => 2,
-            Importance::Important => 3,
-            Importance::VeryImportant => 4
-        };
-
-        self.scoring += match self.duration {
-            Duration::ToBeDefined => 0,
-            Duration::VeryLong => 1,
-            Duration::Long => 2,
-            Duration::Normal => 3,
-            Duration::Short => 4,
-            Duration::VeryShort => 5
-        };
+    // vrai si l'échéance d'une tâche encore ouverte est dépassée
+    pub fn is_overdue(&self) -> bool {
+        !self.status.is_resolved()
+            && self.due_date.is_some_and(|date| deadline(date, self.due_time) < Local::now().naive_local())
+    }
 
-        self.scoring *= match self.status {
-            Status::Canceled =>0,
-            Status::Finished =>1,
-            Status::Blocked =>2,
-            Status::ToDo => 3,
-            Status::InProgress => 4
-        };
+    // calcule le score de la tâche avec le profil de score en vigueur
+    pub fn update_scoring(&mut self, conn: &Connection) -> Result<()> {
+        let waiting = if self.id == 0 { 0 } else { Task::waiting_count(self.id, conn)? };
+        self.scoring = ScoringProfile::load(conn)?.breakdown(self, waiting).total;
+        Ok(())
     }
 
-    // Ramène la liste des tâches
+    // Ramène la liste des tâches (hors corbeille)
     pub fn get_with_filter(conn: &Connection, filter: &Filter) -> Result<Vec<Task>> {
-        let sql_select="SELECT id, description, priority, importance, duration, creation_date, completion_date, start_date, status, grouping, scoring FROM tasks";
+        let sql_select=format!("SELECT {SQL_COLUMNS} FROM {SQL_TASKS} WHERE deleted_at IS NULL");
+        let now = Local::now().naive_local();
+        let end_of_week = now.date() + chrono::Duration::days(6 - i64::from(now.weekday().num_days_from_monday()));
         let (mut stmt,param_list) = match filter {
             Filter::All => (conn.prepare(&format!("{sql_select} ORDER BY scoring desc")).unwrap(),params![]),
-            Filter::DailyWork => (conn.prepare(&format!("{sql_select} WHERE status != ?1 and status != ?2 ORDER BY scoring desc")).unwrap(),params![Status::Finished,Status::Canceled]),
-            Filter::WorkCompleted => (conn.prepare(&format!("{sql_select} WHERE status = ?1 and completion_date = ?2 ORDER BY scoring desc")).unwrap(),params![Status::Finished,Local::now().date_naive()]),
-            Filter::Blocked => (conn.prepare(&format!("{sql_select} WHERE status = ?1 ORDER BY scoring desc")).unwrap(),params![Status::Blocked]),
-            Filter::Quick => (conn.prepare(&format!("{sql_select} WHERE status = ?1 ORDER BY duration desc, scoring desc")).unwrap(),params![Status::ToDo]),
-            Filter::UnClassified => (conn.prepare(&format!("{sql_select} WHERE status != ?1 and (priority = ?2 or importance = ?3 or duration = ?4) ORDER BY duration desc, scoring desc")).unwrap(),params![Status::Finished,Priority::ToBeDefined,Importance::ToBeDefined,Duration::ToBeDefined]),
-            Filter::ByProject => (conn.prepare(&format!("{sql_select} WHERE status != ?1 ORDER BY grouping desc, scoring desc")).unwrap(),params![Status::Finished]),
+            Filter::DailyWork => (conn.prepare(&format!("{sql_select} and status != ?1 and status != ?2 ORDER BY scoring desc")).unwrap(),params![Status::Finished,Status::Canceled]),
+            Filter::WorkCompleted => (conn.prepare(&format!("{sql_select} and status = ?1 and completion_date = ?2 ORDER BY scoring desc")).unwrap(),params![Status::Finished,Local::now().date_naive()]),
+            Filter::Blocked => (conn.prepare(&format!("{sql_select} and status = ?1 ORDER BY scoring desc")).unwrap(),params![Status::Blocked]),
+            Filter::Quick => (conn.prepare(&format!("{sql_select} and status = ?1 ORDER BY duration desc, scoring desc")).unwrap(),params![Status::ToDo]),
+            Filter::UnClassified => (conn.prepare(&format!("{sql_select} and status != ?1 and (priority = ?2 or importance = ?3 or duration = ?4) ORDER BY duration desc, scoring desc")).unwrap(),params![Status::Finished,Priority::ToBeDefined,Importance::ToBeDefined,Duration::ToBeDefined]),
+            Filter::ByProject => (conn.prepare(&format!("{sql_select} and status != ?1 ORDER BY projects.id IS NULL, projects.name COLLATE NOCASE, scoring desc")).unwrap(),params![Status::Finished]),
+            Filter::Overdue => (conn.prepare(&format!("{sql_select} and {SQL_OPEN} and (due_date < ?4 or (due_date = ?4 and due_time < ?5)) ORDER BY due_date, due_time, scoring desc")).unwrap(),params![Status::ToDo,Status::InProgress,Status::Blocked,now.date(),now.time()]),
+            Filter::DueToday => (conn.prepare(&format!("{sql_select} and {SQL_OPEN} and due_date = ?4 ORDER BY due_time, scoring desc")).unwrap(),params![Status::ToDo,Status::InProgress,Status::Blocked,now.date()]),
+            Filter::DueThisWeek => (conn.prepare(&format!("{sql_select} and {SQL_OPEN} and due_date >= ?4 and due_date <= ?5 ORDER BY due_date, due_time, scoring desc")).unwrap(),params![Status::ToDo,Status::InProgress,Status::Blocked,now.date(),end_of_week]),
         };
                 
-        let tasks: Vec<Task> = stmt.query_map(param_list, |row| {
-            Ok(Task {
-                id: row.get("id")?,
-                description: row.get("description")?,
-                priority: row.get("priority")?,
-                importance: row.get("importance")?,
-                duration: row.get("duration")?,
-                creation_date: row.get("creation_date")?,
-                completion_date: row.get("completion_date")?,
-                start_date: row.get("start_date")?,
-                status: row.get("status")?,
-                grouping: row.get("grouping")?,
-                scoring: row.get("scoring")?,
+        let tasks: Vec<Task> = stmt.query_map(param_list, Task::from_row)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(tasks)
+    }
+
+    // Recherche plein texte dans la description, le nom du projet et les notes,
+    // les résultats sont triés par pertinence
+    pub fn search(conn: &Connection, term: &str) -> Result<Vec<SearchHit>> {
+        let query = fts_query(term);
+        if query.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {SQL_COLUMNS},
+                highlight(tasks_fts, 0, ?2, ?3) AS hl_description,
+                highlight(tasks_fts, 1, ?2, ?3) AS hl_grouping,
+                snippet(tasks_fts, 2, ?2, ?3, '…', 12) AS hl_notes
+            FROM {SQL_TASKS} JOIN tasks_fts ON tasks_fts.rowid = tasks.id
+            WHERE tasks_fts MATCH ?1 AND tasks.deleted_at IS NULL
+            ORDER BY rank"
+        ))?;
+        let hits = stmt.query_map(params![query, MATCH_START, MATCH_END], |row| {
+            Ok(SearchHit {
+                task: Task::from_row(row)?,
+                description: row.get("hl_description")?,
+                grouping: row.get("hl_grouping")?,
+                notes: row.get("hl_notes")?,
             })
         })?.collect::<Result<Vec<_>, _>>()?;
-        Ok(tasks)
+        Ok(hits)
     }
 
     // insert les données dans la base
     pub fn insert(&mut self, conn: &Connection) -> Result<usize>{
-        self.update_scoring();
+        self.update_scoring(conn)?;
         self.update_date();
-        conn.execute("INSERT INTO tasks (description, priority, importance, duration, creation_date, completion_date, start_date, status, grouping, scoring) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10);",
+        let tx = Savepoint::new(conn)?;
+        let nb = tx.execute("INSERT INTO tasks (description, priority, importance, duration, creation_date, completion_date, start_date, status, project_id, scoring, parent_id, due_date, due_time, recurrence, previous_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15);",
         (&self.description, &self.priority, &self.importance, 
         &self.duration, &self.creation_date, &self.completion_date, 
-        &self.start_date, &self.status, &self.grouping, &self.scoring),)
+        &self.start_date, &self.status, &self.project_id, &self.scoring, &self.parent_id,
+        &self.due_date, &self.due_time, &self.recurrence, &self.previous_id),)?;
+        *self = Task::get_by_id(tx.last_insert_rowid() as u32, &tx)?;
+        HistoryEntry::record_created(&tx, self)?;
+        tx.commit()?;
+        Ok(nb)
+    }
+
+    // fixe les dates de début et de fin (import), sans passer par update_date
+    pub fn set_dates(id:u32, start_date: Option<NaiveDate>, completion_date: Option<NaiveDate>, conn: &Connection) -> Result<usize>{
+        conn.execute("UPDATE tasks SET start_date = ?1, completion_date = ?2 WHERE id = ?3;", params![start_date, completion_date, id])
+    }
+
+    // fixe les dates de début et de fin données (None : date inchangée), après celles que
+    // update_date déduit du statut, en traçant le changement dans l'historique
+    pub fn change_dates(id:u32, start_date: Option<Option<NaiveDate>>, completion_date: Option<Option<NaiveDate>>, conn: &Connection) -> Result<Task>{
+        let tx = Savepoint::new(conn)?;
+        let old = Task::get_by_id(id, &tx)?;
+        Task::set_dates(id, start_date.unwrap_or(old.start_date), completion_date.unwrap_or(old.completion_date), &tx)?;
+        let task = Task::get_by_id(id, &tx)?;
+        HistoryEntry::record_changes(&tx, EventKind::Updated, &old, &task)?;
+        tx.commit()?;
+        Ok(task)
     }
 
     // lit un id 
     pub fn get_by_id(id:u32,conn: &Connection) -> Result<Task>{
         conn.query_row(
-            "SELECT id, description, priority, importance, duration, creation_date, completion_date, start_date, status, grouping, scoring FROM tasks WHERE id = ?1",
+            &format!("SELECT {SQL_COLUMNS} FROM {SQL_TASKS} WHERE tasks.id = ?1"),
             params![id],
-            |row| {Ok(Task {
-                id: row.get("id")?,
-                description: row.get("description")?,
-                priority: row.get("priority")?,
-                importance: row.get("importance")?,
-                duration: row.get("duration")?,
-                creation_date: row.get("creation_date")?,
-                completion_date: row.get("completion_date")?,
-                start_date: row.get("start_date")?,
-                status: row.get("status")?,
-                grouping: row.get("grouping")?,
-                scoring:  row.get("scoring")?,
-            })},
+            Task::from_row,
         )
     }
 
     // met à jour les données dans la base
     pub fn update(&mut self,id:u32, conn: &Connection) -> Result<usize>{
-        self.update_scoring();
+        self.save(id, EventKind::Updated, conn)
+    }
+
+    // change le statut d'une tâche (les dates de début et de fin suivent le statut)
+    pub fn update_status(id:u32, status: Status, conn: &Connection) -> Result<Task>{
+        let mut task = Task::get_by_id(id, conn)?;
+        task.status = status;
+        task.save(id, EventKind::StatusChanged, conn)?;
+        Ok(task)
+    }
+
+    // enregistre la tâche et trace dans l'historique les champs modifiés
+    fn save(&mut self, id:u32, kind: EventKind, conn: &Connection) -> Result<usize>{
+        self.id = id;
         self.update_date();
-        conn.execute("UPDATE tasks SET description = ?1, priority = ?2, importance = ?3, duration = ?4, creation_date = ?5, completion_date = ?6, start_date = ?7, status = ?8, grouping = ?9, scoring = ?10 WHERE id = ?11;",
+        self.update_scoring(conn)?;
+        let tx = Savepoint::new(conn)?;
+        let old = Task::get_by_id(id, &tx)?;
+        let nb = tx.execute("UPDATE tasks SET description = ?1, priority = ?2, importance = ?3, duration = ?4, creation_date = ?5, completion_date = ?6, start_date = ?7, status = ?8, project_id = ?9, scoring = ?10, parent_id = ?11, due_date = ?12, due_time = ?13, recurrence = ?14 WHERE id = ?15;",
         (&self.description, &self.priority, &self.importance, 
         &self.duration, &self.creation_date, &self.completion_date, 
-        &self.start_date, &self.status, &self.grouping,&self.scoring, id),)
+        &self.start_date, &self.status, &self.project_id,&self.scoring, &self.parent_id,
+        &self.due_date, &self.due_time, &self.recurrence, id),)?;
+        *self = Task::get_by_id(id, &tx)?;
+        HistoryEntry::record_changes(&tx, kind, &old, self)?;
+        tx.commit()?;
+        if self.status != old.status && self.status.is_resolved() {
+            Task::release_dependents(id, conn)?;
+            TimeEntry::stop_for_task(id, conn)?;
+        }
+        if self.status == Status::Finished && old.status != Status::Finished {
+            self.create_next_occurrence(conn)?;
+        }
+        Ok(nb)
     }
 
+    // met la tâche à la corbeille (elle reste en base jusqu'à sa purge)
     pub fn delete(id:u32, conn: &Connection)-> Result<usize>{
-        conn.execute("DELETE FROM tasks WHERE id=?1;",params![id],)
+        let tx = Savepoint::new(conn)?;
+        let nb = tx.execute("UPDATE tasks SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL;",params![Local::now().naive_local(), id],)?;
+        if nb > 0 {
+            HistoryEntry::record_deleted(&tx, &Task::get_by_id(id, &tx)?)?;
+        }
+        tx.commit()?;
+        if nb > 0 {
+            Task::release_dependents(id, conn)?;
+            TimeEntry::stop_for_task(id, conn)?;
+        }
+        Ok(nb)
+    }
+
+    // sort une tâche de la corbeille
+    pub fn restore(id:u32, conn: &Connection)-> Result<usize>{
+        let tx = Savepoint::new(conn)?;
+        let nb = tx.execute("UPDATE tasks SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL;",params![id],)?;
+        if nb > 0 {
+            HistoryEntry::record_restored(&tx, &Task::get_by_id(id, &tx)?)?;
+        }
+        tx.commit()?;
+        if nb > 0 {
+            Task::block_if_waiting(id, conn)?;
+            Task::block_dependents(id, conn)?;
+        }
+        Ok(nb)
+    }
+
+    // tâches de la corbeille, les plus récemment supprimées en premier
+    pub fn get_deleted(conn: &Connection) -> Result<Vec<Task>> {
+        let mut stmt = conn.prepare(&format!("SELECT {SQL_COLUMNS} FROM {SQL_TASKS} WHERE deleted_at IS NOT NULL ORDER BY deleted_at desc"))?;
+        let tasks = stmt.query_map([], Task::from_row)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(tasks)
+    }
+
+    // supprime définitivement une tâche de la corbeille
+    pub fn purge(id:u32, conn: &Connection)-> Result<usize>{
+        let tx = Savepoint::new(conn)?;
+        let task = match Task::get_by_id(id, &tx) {
+            Ok(task) if task.deleted_at.is_some() => task,
+            Ok(_) | Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(0),
+            Err(err) => return Err(err),
+        };
+        // les références à la tâche sont retirées avant elle, les clés étrangères étant actives
+        tx.execute("UPDATE tasks SET parent_id = NULL WHERE parent_id = ?1;",params![id],)?;
+        tx.execute("UPDATE tasks SET previous_id = NULL WHERE previous_id = ?1;",params![id],)?;
+        tx.execute("DELETE FROM task_dependencies WHERE task_id = ?1 OR blocker_id = ?1;",params![id],)?;
+        let nb = tx.execute("DELETE FROM tasks WHERE id = ?1;",params![id],)?;
+        HistoryEntry::record_purged(&tx, &task)?;
+        tx.commit()?;
+        Ok(nb)
+    }
+
+    // tâches dont dépend une tâche (celles qui la bloquent)
+    pub fn get_blockers(id:u32, conn: &Connection) -> Result<Vec<Task>> {
+        let mut stmt = conn.prepare(&format!("SELECT {SQL_COLUMNS} FROM {SQL_TASKS} JOIN task_dependencies ON task_dependencies.blocker_id = tasks.id WHERE task_dependencies.task_id = ?1 ORDER BY tasks.id"))?;
+        let tasks = stmt.query_map(params![id], Task::from_row)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(tasks)
+    }
+
+    // tâches qui dépendent d'une tâche
+    pub fn get_dependents(id:u32, conn: &Connection) -> Result<Vec<Task>> {
+        let mut stmt = conn.prepare(&format!("SELECT {SQL_COLUMNS} FROM {SQL_TASKS} JOIN task_dependencies ON task_dependencies.task_id = tasks.id WHERE task_dependencies.blocker_id = ?1 AND tasks.deleted_at IS NULL ORDER BY tasks.id"))?;
+        let tasks = stmt.query_map(params![id], Task::from_row)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(tasks)
+    }
+
+    // pour chaque tâche, les tâches encore ouvertes qu'elle attend : (id, description)
+    pub fn pending_blockers(conn: &Connection) -> Result<HashMap<u32, Vec<(u32, String)>>> {
+        let mut stmt = conn.prepare("SELECT task_dependencies.task_id, tasks.id, tasks.description FROM task_dependencies JOIN tasks ON tasks.id = task_dependencies.blocker_id WHERE tasks.deleted_at IS NULL AND tasks.status NOT IN (?1, ?2) ORDER BY tasks.id")?;
+        let mut pending: HashMap<u32, Vec<(u32, String)>> = HashMap::new();
+        let rows = stmt.query_map(params![Status::Finished, Status::Canceled], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        for row in rows {
+            let (task_id, blocker_id, description) = row?;
+            pending.entry(task_id).or_default().push((blocker_id, description));
+        }
+        Ok(pending)
+    }
+
+    // vérifie qu'une tâche peut dépendre d'une autre : le bloqueur existe, n'est pas à la
+    // corbeille, n'est pas la tâche elle-même et n'en dépend pas déjà (pas de cycle)
+    pub fn can_depend_on(id:u32, blocker_id:u32, conn: &Connection) -> Result<bool> {
+        match Task::get_by_id(blocker_id, conn) {
+            Ok(blocker) if blocker.deleted_at.is_none() && blocker_id != id => {
+                let cycle: bool = conn.query_row(
+                    "WITH RECURSIVE chain(id) AS (SELECT ?1 UNION SELECT task_dependencies.blocker_id FROM task_dependencies JOIN chain ON task_dependencies.task_id = chain.id) SELECT EXISTS (SELECT 1 FROM chain WHERE id = ?2)",
+                    params![blocker_id, id],
+                    |row| row.get(0),
+                )?;
+                Ok(!cycle)
+            }
+            Ok(_) | Err(rusqlite::Error::QueryReturnedNoRows) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    // ajoute une dépendance (à vérifier avec can_depend_on) ; une tâche à faire ou en cours
+    // qui attend une tâche encore ouverte passe automatiquement au statut bloqué
+    pub fn add_blocker(id:u32, blocker_id:u32, conn: &Connection) -> Result<Task> {
+        let blocker = Task::get_by_id(blocker_id, conn)?;
+        let nb = conn.execute("INSERT OR IGNORE INTO task_dependencies (task_id, blocker_id) VALUES (?1, ?2);", params![id, blocker_id])?;
+        if nb > 0 {
+            HistoryEntry::record_dependency(conn, id, None, Some(&blocker))?;
+            Task::rescore(blocker_id, conn)?;
+        }
+        let task = Task::get_by_id(id, conn)?;
+        if !blocker.status.is_resolved() && matches!(task.status, Status::ToDo | Status::InProgress) {
+            return Task::update_status(id, Status::Blocked, conn);
+        }
+        Ok(task)
+    }
+
+    // retire une dépendance ; la tâche est débloquée si elle n'attend plus rien
+    pub fn remove_blocker(id:u32, blocker_id:u32, conn: &Connection) -> Result<usize> {
+        let nb = conn.execute("DELETE FROM task_dependencies WHERE task_id = ?1 AND blocker_id = ?2;", params![id, blocker_id])?;
+        if nb > 0 {
+            HistoryEntry::record_dependency(conn, id, Some(&Task::get_by_id(blocker_id, conn)?), None)?;
+            Task::rescore(blocker_id, conn)?;
+            Task::release(id, conn)?;
+        }
+        Ok(nb)
+    }
+
+    // repasse à faire une tâche bloquée dont toutes les dépendances sont terminées ou annulées
+    fn release(id:u32, conn: &Connection) -> Result<Option<Task>> {
+        let task = Task::get_by_id(id, conn)?;
+        let pending = Task::get_blockers(id, conn)?
+            .iter()
+            .any(|blocker| blocker.deleted_at.is_none() && !blocker.status.is_resolved());
+        if task.status == Status::Blocked && task.deleted_at.is_none() && !pending {
+            return Ok(Some(Task::update_status(id, Status::ToDo, conn)?));
+        }
+        Ok(None)
     }
 
+    // passe au statut bloqué une tâche à faire ou en cours qui attend une tâche encore ouverte
+    fn block_if_waiting(id:u32, conn: &Connection) -> Result<Option<Task>> {
+        let task = Task::get_by_id(id, conn)?;
+        let pending = Task::get_blockers(id, conn)?
+            .iter()
+            .any(|blocker| blocker.deleted_at.is_none() && !blocker.status.is_resolved());
+        if matches!(task.status, Status::ToDo | Status::InProgress) && task.deleted_at.is_none() && pending {
+            return Ok(Some(Task::update_status(id, Status::Blocked, conn)?));
+        }
+        Ok(None)
+    }
+
+    // rebloque les tâches qui attendent une tâche encore ouverte sortie de la corbeille
+    fn block_dependents(id:u32, conn: &Connection) -> Result<()> {
+        for dependent in Task::get_dependents(id, conn)? {
+            Task::block_if_waiting(dependent.id, conn)?;
+        }
+        Ok(())
+    }
+
+    // débloque les tâches qui attendaient une tâche terminée, annulée ou supprimée
+    fn release_dependents(id:u32, conn: &Connection) -> Result<()> {
+        for dependent in Task::get_dependents(id, conn)? {
+            Task::release(dependent.id, conn)?;
+        }
+        Ok(())
+    }
+
+    // sous-tâches directes d'une tâche (hors corbeille)
+    pub fn get_children(id:u32, conn: &Connection) -> Result<Vec<Task>> {
+        let mut stmt = conn.prepare(&format!("SELECT {SQL_COLUMNS} FROM {SQL_TASKS} WHERE parent_id = ?1 AND deleted_at IS NULL ORDER BY scoring desc"))?;
+        let tasks = stmt.query_map(params![id], Task::from_row)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(tasks)
+    }
+
+    // tâches d'un projet (hors corbeille)
+    pub fn get_by_project(project_id:u32, conn: &Connection) -> Result<Vec<Task>> {
+        let mut stmt = conn.prepare(&format!("SELECT {SQL_COLUMNS} FROM {SQL_TASKS} WHERE project_id = ?1 AND deleted_at IS NULL ORDER BY scoring desc"))?;
+        let tasks = stmt.query_map(params![project_id], Task::from_row)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(tasks)
+    }
+
+    // sous-tâches pas encore terminées (à faire, en cours ou bloquées) d'une tâche
+    pub fn open_children(id:u32, conn: &Connection) -> Result<Vec<Task>> {
+        let mut children = Task::get_children(id, conn)?;
+        children.retain(|child| matches!(child.status, Status::ToDo | Status::InProgress | Status::Blocked));
+        Ok(children)
+    }
+
+    // termine une tâche et, en cascade, toutes ses sous-tâches encore ouvertes ;
+    // retourne les tâches modifiées
+    pub fn finish_with_children(id:u32, conn: &Connection) -> Result<Vec<Task>> {
+        let mut finished = vec![];
+        for child in Task::open_children(id, conn)? {
+            finished.extend(Task::finish_with_children(child.id, conn)?);
+        }
+        finished.push(Task::update_status(id, Status::Finished, conn)?);
+        Ok(finished)
+    }
+
+    // vérifie qu'une tâche peut être rattachée à la parente donnée : la parente existe,
+    // n'est pas à la corbeille et n'est ni la tâche elle-même ni une de ses sous-tâches
+    pub fn can_have_parent(id: Option<u32>, parent_id: u32, conn: &Connection) -> Result<bool> {
+        match Task::get_by_id(parent_id, conn) {
+            Ok(parent) if parent.deleted_at.is_none() => match id {
+                Some(id) => Ok(!Task::descendant_ids(id, conn)?.contains(&parent_id)),
+                None => Ok(true),
+            },
+            Ok(_) | Err(rusqlite::Error::QueryReturnedNoRows) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    // identifiants de la tâche et de toutes ses sous-tâches, à tous les niveaux
+    pub fn descendant_ids(id:u32, conn: &Connection) -> Result<Vec<u32>> {
+        let mut stmt = conn.prepare("WITH RECURSIVE tree(id) AS (SELECT ?1 UNION SELECT tasks.id FROM tasks JOIN tree ON tasks.parent_id = tree.id) SELECT id FROM tree")?;
+        let ids = stmt.query_map(params![id], |row| row.get(0))?.collect::<Result<Vec<_>, _>>()?;
+        Ok(ids)
+    }
+
+    // avancement des tâches qui ont des sous-tâches : (terminées, total hors annulées)
+    pub fn children_progress(conn: &Connection) -> Result<HashMap<u32, (u32, u32)>> {
+        let mut stmt = conn.prepare("SELECT parent_id, SUM(status = ?1), SUM(status != ?2) FROM tasks WHERE parent_id IS NOT NULL AND deleted_at IS NULL GROUP BY parent_id")?;
+        let progress = stmt.query_map(params![Status::Finished, Status::Canceled], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?.collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(progress)
+    }
+
+    // occurrence suivante d'une tâche récurrente, si elle a déjà été créée
+    pub fn get_next_occurrence(id:u32, conn: &Connection) -> Result<Option<Task>> {
+        let mut stmt = conn.prepare(&format!("SELECT {SQL_COLUMNS} FROM {SQL_TASKS} WHERE previous_id = ?1 ORDER BY tasks.id LIMIT 1"))?;
+        stmt.query_map(params![id], Task::from_row)?.next().transpose()
+    }
+
+    // crée l'occurrence suivante d'une tâche récurrente terminée : mêmes caractéristiques,
+    // étiquettes et règle, créée aujourd'hui et due à la prochaine date de la règle.
+    // Une tâche terminée plusieurs fois (réouverte entre temps) ne crée qu'une occurrence
+    fn create_next_occurrence(&self, conn: &Connection) -> Result<Option<Task>> {
+        let Some(recurrence) = &self.recurrence else {
+            return Ok(None);
+        };
+        if Task::get_next_occurrence(self.id, conn)?.is_some() {
+            return Ok(None);
+        }
+        let today = Local::now().date_naive();
+        let mut next = Task {
+            description: self.description.clone(),
+            priority: self.priority,
+            importance: self.importance,
+            duration: self.duration,
+            project_id: self.project_id,
+            parent_id: self.parent_id,
+            due_date: Some(recurrence.next_date(self.due_date, self.completion_date.unwrap_or(today))),
+            due_time: self.due_time,
+            recurrence: Some(recurrence.clone()),
+            previous_id: Some(self.id),
+            ..Task::default()
+        };
+        next.insert(conn)?;
+        Tag::set_for_task(next.id, &Tag::for_task(self.id, conn)?, conn)?;
+        Ok(Some(next))
+    }
+
+    // nombre de tâches encore ouvertes qui attendent une tâche
+    pub fn waiting_count(id:u32, conn: &Connection) -> Result<i64> {
+        conn.query_row(
+            &format!("SELECT COUNT(*) FROM task_dependencies JOIN tasks ON tasks.id = task_dependencies.task_id WHERE task_dependencies.blocker_id = ?4 AND tasks.deleted_at IS NULL AND {SQL_OPEN}"),
+            params![Status::ToDo, Status::InProgress, Status::Blocked, id],
+            |row| row.get(0),
+        )
+    }
+
+    // recalcule le score d'une tâche sans toucher à l'historique ; retourne vrai s'il a changé
+    pub fn rescore(id:u32, conn: &Connection) -> Result<bool> {
+        let mut task = Task::get_by_id(id, conn)?;
+        let old = task.scoring;
+        task.update_scoring(conn)?;
+        if task.scoring == old {
+            return Ok(false);
+        }
+        conn.execute("UPDATE tasks SET scoring = ?1 WHERE id = ?2;", params![task.scoring, id])?;
+        Ok(true)
+    }
+
+    // recalcule le score de toutes les tâches (hors corbeille), après un changement du profil
+    // ou simplement parce que le temps passe (échéance, ancienneté) ; retourne les tâches
+    // dont le score a changé
+    pub fn recompute_scores(conn: &Connection) -> Result<Vec<u32>> {
+        let tx = Savepoint::new(conn)?;
+        let mut changed = vec![];
+        for task in Task::get_with_filter(&tx, &Filter::All)? {
+            if Task::rescore(task.id, &tx)? {
+                changed.push(task.id);
+            }
+        }
+        tx.commit()?;
+        Ok(changed)
+    }
+
+    // supprime définitivement les tâches mises à la corbeille avant la date donnée
+    pub fn purge_deleted_before(limit: NaiveDateTime, conn: &Connection)-> Result<usize>{
+        let ids = {
+            let mut stmt = conn.prepare("SELECT id FROM tasks WHERE deleted_at IS NOT NULL AND deleted_at < ?1")?;
+            stmt.query_map(params![limit], |row| row.get::<_, u32>(0))?.collect::<Result<Vec<_>, _>>()?
+        };
+        let mut nb = 0;
+        for id in ids {
+            nb += Task::purge(id, conn)?;
+        }
+        Ok(nb)
+    }
+
+}
+
+// Transforme la saisie de l'utilisateur en requête fts5 : chaque mot est cherché comme préfixe
+// et mis entre guillemets pour que la ponctuation ne soit pas interprétée comme de la syntaxe
+
+fn fts_query(term: &str) -> String {
+    term.split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Réordonne une liste de tâches pour afficher chaque sous-tâche sous sa tâche parente,
+// en conservant l'ordre de la liste à chaque niveau. Retourne les tâches avec leur profondeur
+// (une tâche dont la parente n'est pas dans la liste est affichée au premier niveau).
+
+pub fn tree_order(tasks: Vec<Task>) -> Vec<(Task, usize)> {
+    let ids: Vec<u32> = tasks.iter().map(|task| task.id).collect();
+    let mut children: HashMap<u32, Vec<Task>> = HashMap::new();
+    let mut roots = vec![];
+    for task in tasks {
+        match task.parent_id {
+            Some(parent_id) if ids.contains(&parent_id) && parent_id != task.id => children.entry(parent_id).or_default().push(task),
+            _ => roots.push(task),
+        }
+    }
+    let mut ordered = vec![];
+    let mut stack: Vec<(Task, usize)> = roots.into_iter().rev().map(|task| (task, 0)).collect();
+    while let Some((task, depth)) = stack.pop() {
+        if let Some(subtasks) = children.remove(&task.id) {
+            stack.extend(subtasks.into_iter().rev().map(|child| (child, depth + 1)));
+        }
+        ordered.push((task, depth));
+    }
+    // une boucle de parenté ne doit pas faire disparaître de tâches
+    ordered.extend(children.into_values().flatten().map(|task| (task, 0)));
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::migration::test_database;
+
+    fn add(description: &str, conn: &Connection) -> Task {
+        let mut task = Task { description: description.to_string(), ..Task::default() };
+        task.insert(conn).unwrap();
+        task
+    }
+
+    #[test]
+    fn trash_and_restore() {
+        let conn = test_database();
+        let task = add("Corbeille", &conn);
+        assert_eq!(Task::delete(task.id, &conn).unwrap(), 1);
+        // une tâche déjà à la corbeille n'y est pas remise
+        assert_eq!(Task::delete(task.id, &conn).unwrap(), 0);
+        assert!(Task::get_with_filter(&conn, &Filter::All).unwrap().is_empty());
+        assert_eq!(Task::get_deleted(&conn).unwrap().iter().map(|task| task.id).collect::<Vec<_>>(), vec![task.id]);
+
+        assert_eq!(Task::restore(task.id, &conn).unwrap(), 1);
+        assert_eq!(Task::restore(task.id, &conn).unwrap(), 0);
+        assert!(Task::get_deleted(&conn).unwrap().is_empty());
+        assert_eq!(Task::get_by_id(task.id, &conn).unwrap().deleted_at, None);
+        let kinds: Vec<EventKind> = HistoryEntry::for_task(task.id, &conn).unwrap().iter().map(|entry| entry.kind).collect();
+        assert_eq!(kinds, vec![EventKind::Restored, EventKind::Deleted, EventKind::Created]);
+    }
+
+    // la mise à la corbeille arrête le chronomètre et débloque les tâches qui l'attendaient ;
+    // la restauration les rebloque
+    #[test]
+    fn trash_releases_dependents() {
+        let conn = test_database();
+        let blocker = add("Bloquante", &conn);
+        let waiting = add("En attente", &conn);
+        assert_eq!(Task::add_blocker(waiting.id, blocker.id, &conn).unwrap().status, Status::Blocked);
+        TimeEntry::start(blocker.id, &conn).unwrap();
+
+        Task::delete(blocker.id, &conn).unwrap();
+        assert_eq!(Task::get_by_id(waiting.id, &conn).unwrap().status, Status::ToDo);
+        assert!(TimeEntry::running(&conn).unwrap().is_none());
+
+        Task::restore(blocker.id, &conn).unwrap();
+        assert_eq!(Task::get_by_id(waiting.id, &conn).unwrap().status, Status::Blocked);
+    }
+
+    #[test]
+    fn purge_keeps_history() {
+        let conn = test_database();
+        let task = add("Purgée", &conn);
+        Task::delete(task.id, &conn).unwrap();
+        assert_eq!(Task::purge(task.id, &conn).unwrap(), 1);
+        assert_eq!(Task::purge(task.id, &conn).unwrap(), 0);
+        assert_eq!(HistoryEntry::for_task(task.id, &conn).unwrap()[0].kind, EventKind::Purged);
+    }
+
+    // pas de dépendance envers soi-même, une tâche de la corbeille ou qui ferme un cycle
+    #[test]
+    fn dependency_cycles() {
+        let conn = test_database();
+        let [a, b, c, trashed] = ["A", "B", "C", "Corbeille"].map(|description| add(description, &conn));
+        Task::delete(trashed.id, &conn).unwrap();
+        assert!(Task::can_depend_on(a.id, b.id, &conn).unwrap());
+        Task::add_blocker(a.id, b.id, &conn).unwrap();
+        Task::add_blocker(b.id, c.id, &conn).unwrap();
+        assert!(!Task::can_depend_on(a.id, a.id, &conn).unwrap());
+        assert!(!Task::can_depend_on(b.id, a.id, &conn).unwrap());
+        assert!(!Task::can_depend_on(c.id, a.id, &conn).unwrap());
+        assert!(!Task::can_depend_on(a.id, trashed.id, &conn).unwrap());
+        assert!(!Task::can_depend_on(a.id, 999, &conn).unwrap());
+        assert!(Task::can_depend_on(a.id, c.id, &conn).unwrap());
+    }
+
+    // une tâche bloquée repasse à faire quand toutes ses dépendances sont terminées ou annulées
+    #[test]
+    fn automatic_unblock() {
+        let conn = test_database();
+        let [task, first, second] = ["Attend", "Première", "Seconde"].map(|description| add(description, &conn));
+        Task::add_blocker(task.id, first.id, &conn).unwrap();
+        assert_eq!(Task::add_blocker(task.id, second.id, &conn).unwrap().status, Status::Blocked);
+        assert_eq!(Task::waiting_count(first.id, &conn).unwrap(), 1);
+        assert_eq!(Task::pending_blockers(&conn).unwrap()[&task.id].len(), 2);
+
+        Task::update_status(first.id, Status::Finished, &conn).unwrap();
+        assert_eq!(Task::get_by_id(task.id, &conn).unwrap().status, Status::Blocked);
+        Task::update_status(second.id, Status::Canceled, &conn).unwrap();
+        assert_eq!(Task::get_by_id(task.id, &conn).unwrap().status, Status::ToDo);
+        assert!(!Task::pending_blockers(&conn).unwrap().contains_key(&task.id));
+
+        // une dépendance envers une tâche terminée ne bloque pas
+        let other = add("Autre", &conn);
+        assert_eq!(Task::add_blocker(other.id, first.id, &conn).unwrap().status, Status::ToDo);
+    }
+
+    #[test]
+    fn remove_blocker_unblocks() {
+        let conn = test_database();
+        let [task, blocker] = ["Attend", "Bloquante"].map(|description| add(description, &conn));
+        Task::add_blocker(task.id, blocker.id, &conn).unwrap();
+        assert_eq!(Task::remove_blocker(task.id, blocker.id, &conn).unwrap(), 1);
+        assert_eq!(Task::get_by_id(task.id, &conn).unwrap().status, Status::ToDo);
+        assert_eq!(Task::remove_blocker(task.id, blocker.id, &conn).unwrap(), 0);
+    }
+
+    // une tâche encore référencée (sous-tâche, occurrence suivante, dépendance) se purge
+    // malgré les clés étrangères
+    #[test]
+    fn purge_referenced_task() {
+        let conn = test_database();
+        let parent = add("Parente", &conn);
+        let mut child = Task { description: "Sous-tâche".to_string(), parent_id: Some(parent.id), ..Task::default() };
+        child.insert(&conn).unwrap();
+        let mut recurring = Task { description: "Chaque jour".to_string(), recurrence: Some(Recurrence::Daily), ..Task::default() };
+        recurring.insert(&conn).unwrap();
+        Task::update_status(recurring.id, Status::Finished, &conn).unwrap();
+        let next = Task::get_next_occurrence(recurring.id, &conn).unwrap().unwrap();
+        let blocker = add("Bloquante", &conn);
+        Task::add_blocker(child.id, blocker.id, &conn).unwrap();
+
+        for id in [parent.id, recurring.id, blocker.id] {
+            Task::delete(id, &conn).unwrap();
+            assert_eq!(Task::purge(id, &conn).unwrap(), 1);
+            assert!(Task::get_by_id(id, &conn).is_err());
+        }
+        let child = Task::get_by_id(child.id, &conn).unwrap();
+        assert_eq!(child.parent_id, None);
+        assert!(Task::get_blockers(child.id, &conn).unwrap().is_empty());
+        assert_eq!(Task::get_by_id(next.id, &conn).unwrap().previous_id, None);
+    }
+
+    #[test]
+    fn purge_deleted_before() {
+        let conn = test_database();
+        let parent = add("Parente", &conn);
+        let mut child = Task { description: "Sous-tâche".to_string(), parent_id: Some(parent.id), ..Task::default() };
+        child.insert(&conn).unwrap();
+        let kept = add("Gardée", &conn);
+        Task::delete(parent.id, &conn).unwrap();
+        Task::delete(child.id, &conn).unwrap();
+        // une tâche hors corbeille n'est jamais purgée
+        assert_eq!(Task::purge(kept.id, &conn).unwrap(), 0);
+        let limit = Local::now().naive_local() + chrono::Duration::minutes(1);
+        assert_eq!(Task::purge_deleted_before(limit, &conn).unwrap(), 2);
+        assert!(Task::get_deleted(&conn).unwrap().is_empty());
+        assert!(Task::get_by_id(kept.id, &conn).is_ok());
+    }
 }
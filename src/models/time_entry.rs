@@ -0,0 +1,246 @@
+use crate::models::task::{Status, Task};
+use crate::models::savepoint::Savepoint;
+
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use rusqlite::{Connection, OptionalExtension, Result, Row, params};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Temps passé sur les tâches : chaque démarrage du chronomètre crée une ligne, fermée à
+// son arrêt. Un seul chronomètre tourne à la fois, en démarrer un arrête le précédent
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub id: u32,
+    pub task_id: u32,
+    pub started_at: NaiveDateTime,
+    pub stopped_at: Option<NaiveDateTime>,
+    // durée en secondes (jusqu'à maintenant pour le chronomètre en cours)
+    pub seconds: i64,
+}
+
+// Chronomètre en cours, diffusé aux pages ouvertes
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunningTimer {
+    pub task_id: u32,
+    pub description: String,
+    pub started_at: NaiveDateTime,
+}
+
+// Une ligne de la feuille de temps d'une journée : le temps passé sur une tâche ce jour-là
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimesheetLine {
+    pub task: Task,
+    pub seconds: i64,
+    pub entries: Vec<TimeEntry>,
+}
+
+const SQL_SELECT: &str = "SELECT id, task_id, started_at, stopped_at FROM time_entries";
+
+fn seconds(started_at: NaiveDateTime, stopped_at: Option<NaiveDateTime>) -> i64 {
+    (stopped_at.unwrap_or_else(|| Local::now().naive_local()) - started_at).num_seconds().max(0)
+}
+
+impl TimeEntry {
+
+    fn from_row(row: &Row) -> Result<TimeEntry> {
+        let started_at = row.get("started_at")?;
+        let stopped_at = row.get("stopped_at")?;
+        Ok(TimeEntry {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            started_at,
+            stopped_at,
+            seconds: seconds(started_at, stopped_at),
+        })
+    }
+
+    // chronomètre en cours, s'il y en a un
+    pub fn running(conn: &Connection) -> Result<Option<RunningTimer>> {
+        conn.query_row(
+            "SELECT time_entries.task_id, tasks.description, time_entries.started_at FROM time_entries
+            JOIN tasks ON tasks.id = time_entries.task_id WHERE time_entries.stopped_at IS NULL",
+            [],
+            |row| Ok(RunningTimer { task_id: row.get(0)?, description: row.get(1)?, started_at: row.get(2)? }),
+        )
+        .optional()
+    }
+
+    // démarre le chronomètre d'une tâche, une tâche à faire passe en cours. Retourne les
+    // tâches dont le chronomètre a changé (celle arrêtée et celle démarrée)
+    pub fn start(task_id: u32, conn: &Connection) -> Result<Vec<u32>> {
+        let running = TimeEntry::running(conn)?;
+        if running.as_ref().is_some_and(|timer| timer.task_id == task_id) {
+            return Ok(vec![]);
+        }
+        let tx = Savepoint::new(conn)?;
+        let stopped = TimeEntry::stop(&tx)?;
+        tx.execute(
+            "INSERT INTO time_entries (task_id, started_at) VALUES (?1, ?2);",
+            params![task_id, Local::now().naive_local()],
+        )?;
+        tx.commit()?;
+        if Task::get_by_id(task_id, conn)?.status == Status::ToDo {
+            Task::update_status(task_id, Status::InProgress, conn)?;
+        }
+        Ok(stopped.into_iter().chain([task_id]).collect())
+    }
+
+    // arrête le chronomètre en cours et retourne la tâche concernée
+    pub fn stop(conn: &Connection) -> Result<Option<u32>> {
+        let running = TimeEntry::running(conn)?;
+        conn.execute(
+            "UPDATE time_entries SET stopped_at = ?1 WHERE stopped_at IS NULL;",
+            params![Local::now().naive_local()],
+        )?;
+        Ok(running.map(|timer| timer.task_id))
+    }
+
+    // arrête le chronomètre de la tâche s'il tourne (tâche terminée ou supprimée)
+    pub fn stop_for_task(task_id: u32, conn: &Connection) -> Result<bool> {
+        let nb = conn.execute(
+            "UPDATE time_entries SET stopped_at = ?1 WHERE stopped_at IS NULL AND task_id = ?2;",
+            params![Local::now().naive_local(), task_id],
+        )?;
+        Ok(nb > 0)
+    }
+
+    // temps passé sur une tâche, du plus récent au plus ancien
+    pub fn for_task(task_id: u32, conn: &Connection) -> Result<Vec<TimeEntry>> {
+        let mut stmt = conn.prepare(&format!("{SQL_SELECT} WHERE task_id = ?1 ORDER BY started_at desc"))?;
+        let entries = stmt.query_map(params![task_id], TimeEntry::from_row)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    // toutes les lignes de temps, dans l'ordre de démarrage (sauvegarde)
+    pub fn get_all(conn: &Connection) -> Result<Vec<TimeEntry>> {
+        let mut stmt = conn.prepare(&format!("{SQL_SELECT} ORDER BY started_at, id"))?;
+        let entries = stmt.query_map([], TimeEntry::from_row)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    // temps total passé en secondes, par tâche
+    pub fn totals(conn: &Connection) -> Result<HashMap<u32, i64>> {
+        let mut stmt = conn.prepare(SQL_SELECT)?;
+        let mut totals = HashMap::new();
+        for entry in stmt.query_map([], TimeEntry::from_row)? {
+            let entry = entry?;
+            *totals.entry(entry.task_id).or_default() += entry.seconds;
+        }
+        Ok(totals)
+    }
+
+    // supprime une ligne de temps d'une tâche
+    pub fn delete(id: u32, task_id: u32, conn: &Connection) -> Result<usize> {
+        conn.execute("DELETE FROM time_entries WHERE id = ?1 AND task_id = ?2;", params![id, task_id])
+    }
+
+    // feuille de temps d'une journée : les lignes sont coupées à minuit, les tâches
+    // sont triées par temps passé décroissant
+    pub fn timesheet(day: NaiveDate, conn: &Connection) -> Result<Vec<TimesheetLine>> {
+        let (start, end) = (day.and_hms_opt(0, 0, 0).unwrap_or_default(), (day + chrono::Days::new(1)).and_hms_opt(0, 0, 0).unwrap_or_default());
+        let mut stmt = conn.prepare(&format!(
+            "{SQL_SELECT} WHERE started_at < ?2 AND (stopped_at IS NULL OR stopped_at > ?1) ORDER BY started_at"
+        ))?;
+        let entries = stmt.query_map(params![start, end], TimeEntry::from_row)?.collect::<Result<Vec<_>, _>>()?;
+        let mut lines: Vec<TimesheetLine> = vec![];
+        for mut entry in entries {
+            let now = Local::now().naive_local();
+            entry.seconds = seconds(entry.started_at.max(start), Some(entry.stopped_at.unwrap_or(now).min(end)));
+            match lines.iter_mut().find(|line| line.task.id == entry.task_id) {
+                Some(line) => {
+                    line.seconds += entry.seconds;
+                    line.entries.push(entry);
+                }
+                None => lines.push(TimesheetLine {
+                    task: Task::get_by_id(entry.task_id, conn)?,
+                    seconds: entry.seconds,
+                    entries: vec![entry],
+                }),
+            }
+        }
+        lines.sort_by_key(|line| -line.seconds);
+        Ok(lines)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::migration::test_database;
+
+    fn add(description: &str, conn: &Connection) -> Task {
+        let mut task = Task { description: description.to_string(), ..Task::default() };
+        task.insert(conn).unwrap();
+        task
+    }
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn entry(task: &Task, started_at: &str, stopped_at: &str, conn: &Connection) {
+        conn.execute(
+            "INSERT INTO time_entries (task_id, started_at, stopped_at) VALUES (?1, ?2, ?3);",
+            params![task.id, at(started_at), at(stopped_at)],
+        )
+        .unwrap();
+    }
+
+    // un seul chronomètre à la fois, une tâche à faire passe en cours
+    #[test]
+    fn start_and_stop() {
+        let conn = test_database();
+        let [first, second] = ["Première", "Seconde"].map(|description| add(description, &conn));
+        assert_eq!(TimeEntry::start(first.id, &conn).unwrap(), vec![first.id]);
+        assert_eq!(Task::get_by_id(first.id, &conn).unwrap().status, Status::InProgress);
+        // redémarrer la même tâche ne change rien
+        assert!(TimeEntry::start(first.id, &conn).unwrap().is_empty());
+        assert_eq!(TimeEntry::start(second.id, &conn).unwrap(), vec![first.id, second.id]);
+        assert_eq!(TimeEntry::running(&conn).unwrap().unwrap().task_id, second.id);
+        assert!(TimeEntry::for_task(first.id, &conn).unwrap()[0].stopped_at.is_some());
+
+        Task::update_status(second.id, Status::Finished, &conn).unwrap();
+        assert!(TimeEntry::running(&conn).unwrap().is_none());
+        assert_eq!(TimeEntry::stop(&conn).unwrap(), None);
+    }
+
+    #[test]
+    fn totals() {
+        let conn = test_database();
+        let [first, second] = ["Première", "Seconde"].map(|description| add(description, &conn));
+        entry(&first, "2026-03-02 09:00", "2026-03-02 09:30", &conn);
+        entry(&first, "2026-03-03 14:00", "2026-03-03 15:15", &conn);
+        entry(&second, "2026-03-02 10:00", "2026-03-02 10:20", &conn);
+        let totals = TimeEntry::totals(&conn).unwrap();
+        assert_eq!((totals[&first.id], totals[&second.id]), (105 * 60, 20 * 60));
+
+        let id = TimeEntry::for_task(first.id, &conn).unwrap()[0].id;
+        // une ligne n'est supprimée que pour sa tâche
+        assert_eq!(TimeEntry::delete(id, second.id, &conn).unwrap(), 0);
+        assert_eq!(TimeEntry::delete(id, first.id, &conn).unwrap(), 1);
+        assert_eq!(TimeEntry::totals(&conn).unwrap()[&first.id], 30 * 60);
+    }
+
+    // les lignes à cheval sur minuit ne comptent que pour la journée, les tâches sont
+    // triées par temps passé décroissant
+    #[test]
+    fn timesheet() {
+        let conn = test_database();
+        let [night, day, other] = ["Nuit", "Journée", "Autre jour"].map(|description| add(description, &conn));
+        entry(&night, "2026-03-01 23:00", "2026-03-02 01:00", &conn);
+        entry(&day, "2026-03-02 08:00", "2026-03-02 09:00", &conn);
+        entry(&day, "2026-03-02 14:00", "2026-03-02 14:45", &conn);
+        entry(&night, "2026-03-02 23:30", "2026-03-03 02:00", &conn);
+        entry(&other, "2026-03-03 08:00", "2026-03-03 09:00", &conn);
+
+        let date = at("2026-03-02 00:00").date();
+        let lines = TimeEntry::timesheet(date, &conn).unwrap();
+        let seconds: Vec<(u32, i64, usize)> = lines.iter().map(|line| (line.task.id, line.seconds, line.entries.len())).collect();
+        assert_eq!(seconds, vec![(day.id, 105 * 60, 2), (night.id, 90 * 60, 2)]);
+        assert!(TimeEntry::timesheet(date - chrono::Days::new(5), &conn).unwrap().is_empty());
+    }
+}
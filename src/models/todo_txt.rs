@@ -0,0 +1,279 @@
+use crate::models::csv_transfer::{self, ImportRow};
+use crate::models::project::Project;
+use crate::models::task::{Priority, Status, Task};
+
+use chrono::NaiveDate;
+use rusqlite::{Connection, Result};
+use std::collections::HashMap;
+
+// Tâches au format todo.txt, une par ligne :
+// x 2026-09-05 2026-09-01 (A) Description +Projet @étiquette due:2026-09-10
+// La priorité A à D vient de Priority, +projet du projet et @contexte des étiquettes ;
+// les espaces des noms deviennent des « _ ». Les extensions clé:valeur gardent
+// l'échéance (due), le statut autre que à faire ou fini (status) et la récurrence (rec)
+
+const PRIORITY_LETTERS: [(Priority, char); 4] = [
+    (Priority::VeryUrgent, 'A'),
+    (Priority::Urgent, 'B'),
+    (Priority::Normal, 'C'),
+    (Priority::NotUrgent, 'D'),
+];
+
+const STATUS_KEYS: [(Status, &str); 3] = [
+    (Status::InProgress, "in_progress"),
+    (Status::Blocked, "blocked"),
+    (Status::Canceled, "canceled"),
+];
+
+// nom utilisable dans un mot todo.txt (+projet, @contexte)
+fn word(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+// ligne todo.txt d'une tâche
+pub fn line(task: &Task, tags: &[String]) -> String {
+    let mut parts: Vec<String> = vec![];
+    if task.status.is_resolved() {
+        // la date de fin est toujours écrite, sinon la date de création serait relue comme date de fin
+        parts.push("x".to_string());
+        parts.push(task.completion_date.unwrap_or(task.creation_date).to_string());
+    } else if let Some((_, letter)) = PRIORITY_LETTERS.iter().find(|(priority, _)| *priority == task.priority) {
+        parts.push(format!("({letter})"));
+    }
+    parts.push(task.creation_date.to_string());
+    parts.push(task.description.split_whitespace().collect::<Vec<_>>().join(" "));
+    if !task.grouping.is_empty() {
+        parts.push(format!("+{}", word(&task.grouping)));
+    }
+    parts.extend(tags.iter().map(|tag| format!("@{}", word(tag))));
+    // la priorité d'une tâche terminée est gardée en extension (la lettre ne précède pas « x »)
+    if task.status.is_resolved()
+        && let Some((_, letter)) = PRIORITY_LETTERS.iter().find(|(priority, _)| *priority == task.priority)
+    {
+        parts.push(format!("pri:{letter}"));
+    }
+    if let Some(due_date) = task.due_date {
+        parts.push(format!("due:{due_date}"));
+    }
+    if let Some((_, key)) = STATUS_KEYS.iter().find(|(status, _)| *status == task.status) {
+        parts.push(format!("status:{key}"));
+    }
+    if let Some(recurrence) = &task.recurrence {
+        parts.push(format!("rec:{recurrence}"));
+    }
+    parts.join(" ")
+}
+
+// fichier todo.txt des tâches données
+pub fn export(tasks: &[Task], task_tags: &HashMap<u32, Vec<String>>) -> String {
+    let empty = vec![];
+    tasks
+        .iter()
+        .map(|task| line(task, task_tags.get(&task.id).unwrap_or(&empty)) + "\n")
+        .collect()
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+}
+
+fn parse_priority(value: &str) -> Option<Priority> {
+    let mut chars = value.chars();
+    let letter = chars.next()?.to_ascii_uppercase();
+    if chars.next().is_some() {
+        return None;
+    }
+    PRIORITY_LETTERS.iter().find(|(_, known)| *known == letter).map(|(priority, _)| *priority)
+}
+
+// Lit un fichier todo.txt, une ligne d'import par ligne non vide. Les +projet sont
+// rapprochés des projets existants (les « _ » valant des espaces)
+pub fn parse(content: &str, projects: &[Project]) -> Vec<ImportRow> {
+    let mut rows = vec![];
+    for (i, text) in content.trim_start_matches('\u{feff}').lines().enumerate() {
+        if text.trim().is_empty() {
+            continue;
+        }
+        let mut row = ImportRow {
+            line: i + 1,
+            task: Task { description: String::new(), ..Task::default() },
+            project: String::new(),
+            tags: vec![],
+            errors: vec![],
+        };
+        let task = &mut row.task;
+        let mut words = text.split_whitespace().peekable();
+
+        // en-tête : « x date_fin date_création » ou « (A) date_création »
+        if words.peek() == Some(&"x") {
+            words.next();
+            task.status = Status::Finished;
+            let first = words.peek().copied().and_then(parse_date);
+            if first.is_some() {
+                words.next();
+                task.completion_date = first;
+                if let Some(creation_date) = words.peek().copied().and_then(parse_date) {
+                    words.next();
+                    task.creation_date = creation_date;
+                }
+            }
+        } else {
+            let priority = words
+                .peek()
+                .and_then(|first| first.strip_prefix('(')?.strip_suffix(')'))
+                .and_then(parse_priority);
+            if let Some(priority) = priority {
+                words.next();
+                task.priority = priority;
+            }
+            if let Some(creation_date) = words.peek().copied().and_then(parse_date) {
+                words.next();
+                task.creation_date = creation_date;
+            }
+        }
+
+        let mut description = vec![];
+        for word in words {
+            if let Some(name) = word.strip_prefix('+').filter(|name| !name.is_empty()) {
+                if row.project.is_empty() {
+                    row.project = projects
+                        .iter()
+                        .find(|project| self::word(&project.name).to_lowercase() == name.to_lowercase())
+                        .map_or_else(|| name.replace('_', " "), |project| project.name.clone());
+                }
+                continue;
+            }
+            if let Some(name) = word.strip_prefix('@').filter(|name| !name.is_empty()) {
+                let name = name.replace('_', " ");
+                if !row.tags.iter().any(|known| known.to_lowercase() == name.to_lowercase()) {
+                    row.tags.push(name);
+                }
+                continue;
+            }
+            let invalid = |key: &str, value: &str| format!("{key} : valeur invalide « {value} »");
+            match word.split_once(':') {
+                Some(("due", value)) => match parse_date(value) {
+                    Some(date) => task.due_date = Some(date),
+                    None => row.errors.push(invalid("due", value)),
+                },
+                Some(("pri", value)) => match parse_priority(value) {
+                    Some(priority) => task.priority = priority,
+                    None => row.errors.push(invalid("pri", value)),
+                },
+                Some(("status", value)) => match STATUS_KEYS.iter().find(|(_, key)| *key == value) {
+                    Some((status, _)) => task.status = *status,
+                    None => row.errors.push(invalid("status", value)),
+                },
+                Some(("rec", value)) => match value.parse() {
+                    Ok(recurrence) => task.recurrence = Some(recurrence),
+                    Err(message) => row.errors.push(message),
+                },
+                _ => description.push(word),
+            }
+        }
+        task.description = description.join(" ");
+        if task.description.is_empty() {
+            row.errors.push("La description est obligatoire".to_string());
+        }
+        rows.push(row);
+    }
+    rows
+}
+
+// Importe les lignes du fichier todo.txt, tout ou rien. Retourne les tâches créées, ou
+// les erreurs précédées du numéro de ligne
+pub fn import(content: &str, conn: &Connection) -> Result<std::result::Result<Vec<Task>, Vec<String>>> {
+    let rows = parse(content, &Project::get_all(true, conn)?);
+    if rows.is_empty() {
+        return Ok(Err(vec!["Aucune tâche dans le fichier".to_string()]));
+    }
+    let errors = csv_transfer::row_errors(&rows);
+    if !errors.is_empty() {
+        return Ok(Err(errors));
+    }
+    let ids = csv_transfer::import(&rows, conn)?;
+    let tasks = ids.into_iter().map(|id| Task::get_by_id(id, conn)).collect::<Result<Vec<_>>>()?;
+    Ok(Ok(tasks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::recurrence::Recurrence;
+
+    fn date(value: &str) -> NaiveDate {
+        parse_date(value).unwrap()
+    }
+
+    // écrit la tâche en todo.txt puis relit la ligne
+    fn round_trip(task: &Task, tags: &[String]) -> ImportRow {
+        let projects = [Project { name: "Maison neuve".to_string(), ..Project::default() }];
+        let mut rows = parse(&line(task, tags), &projects);
+        assert_eq!(rows.len(), 1);
+        rows.remove(0)
+    }
+
+    fn assert_same(task: &Task, row: &ImportRow) {
+        assert!(row.errors.is_empty(), "{:?}", row.errors);
+        assert_eq!(row.task.description, task.description);
+        assert_eq!(row.task.status, task.status);
+        assert_eq!(row.task.priority, task.priority);
+        assert_eq!(row.task.creation_date, task.creation_date);
+        assert_eq!(row.task.due_date, task.due_date);
+        assert_eq!(row.task.recurrence, task.recurrence);
+    }
+
+    #[test]
+    fn open_task() {
+        let task = Task {
+            description: "Appeler le plombier".to_string(),
+            priority: Priority::Urgent,
+            status: Status::InProgress,
+            creation_date: date("2026-09-01"),
+            due_date: Some(date("2026-09-10")),
+            recurrence: Some(Recurrence::Weekly(vec![chrono::Weekday::Mon, chrono::Weekday::Thu])),
+            grouping: "Maison neuve".to_string(),
+            ..Task::default()
+        };
+        let row = round_trip(&task, &["au téléphone".to_string()]);
+        assert_same(&task, &row);
+        assert_eq!(row.project, "Maison neuve");
+        assert_eq!(row.tags, vec!["au téléphone".to_string()]);
+    }
+
+    #[test]
+    fn finished_task() {
+        let task = Task {
+            description: "Payer la facture".to_string(),
+            priority: Priority::VeryUrgent,
+            status: Status::Finished,
+            creation_date: date("2026-09-01"),
+            completion_date: Some(date("2026-09-05")),
+            ..Task::default()
+        };
+        let row = round_trip(&task, &[]);
+        assert_same(&task, &row);
+        assert_eq!(row.task.completion_date, task.completion_date);
+    }
+
+    // sans date de fin, la date de création ne doit pas être prise pour la date de fin
+    #[test]
+    fn resolved_task_without_completion_date() {
+        for status in [Status::Finished, Status::Canceled] {
+            let task = Task {
+                description: "Ranger le garage".to_string(),
+                status,
+                creation_date: date("2026-08-20"),
+                ..Task::default()
+            };
+            assert_same(&task, &round_trip(&task, &[]));
+        }
+    }
+
+    #[test]
+    fn invalid_extension() {
+        let rows = parse("Tâche due:demain status:perdu", &[]);
+        assert_eq!(rows[0].errors.len(), 2);
+        assert_eq!(rows[0].task.description, "Tâche");
+    }
+}
@@ -1,7 +1,9 @@
-use std::env;
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use minijinja::{value::Value, Error};
 
+use crate::models::recurrence::Recurrence;
+use crate::models::task::{MATCH_END, MATCH_START, Status, deadline};
+
 // Macro permettant de déclarer un enum automatiquement et d'implémenter les traits pour sql
 // et pour avoir un texte associé à l'enum
 
@@ -15,10 +17,10 @@ macro_rules! enum_with_strings {
         }
 
         // retourne le texte associé à la valeur de l'enum (pour l'affichage dans les vues)
-        impl ToString for $name {
-            fn to_string(&self) -> String {
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                 match self {
-                    $(Self::$variant => $string.to_string()),*
+                    $(Self::$variant => write!(f, "{}", $string)),*
                 }
             }
         }
@@ -64,32 +66,47 @@ pub fn parse_optional_date(s: &str) -> Result<Option<NaiveDate>, chrono::ParseEr
     }
 }
 
-pub fn print_usage(){
-    println!("Usage :");
-    println!("todo [-p port]");
-    println!("Ex :");
-    println!("todo -p 8080");
-}
+// heure saisie dans un champ time (hh:mm, ou hh:mm:ss)
 
-pub fn get_port_from_args() -> Result<u16, String> {
-    let default_port = 3000;
-    let args: Vec<String> = env::args().collect();
-    
-    if let Some(pos) = args.iter().position(|arg| arg == "--port" || arg == "-p") {
-        if let Some(port_str) = args.get(pos + 1) {
-            match port_str.parse::<u16>() {
-                Ok(port) if port > 79 => Ok(port),
-                Ok(_) => Err("Error : port must be greater than 79.".to_string()),
-                Err(_) => Err("Erreur : invalid port.".to_string()),
-            }
-        } else {
-            Err("Erreur : no port given after --port or -p.".to_string())
-        }
+pub fn parse_optional_time(s: &str) -> Result<Option<NaiveTime>, chrono::ParseError> {
+    if s.trim().is_empty() {
+        Ok(None)
     } else {
-        Ok(default_port)
+        NaiveTime::parse_from_str(s, "%H:%M:%S")
+            .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M"))
+            .map(Some)
     }
 }
 
+pub fn print_usage(){
+    println!("Usage :");
+    println!("todo [serve] [-p port] [--db path] [--retention days]");
+    println!("todo add \"description\" [--priority p] [--importance i] [--duration d] [--project nom]");
+    println!("         [--due AAAA-MM-JJ] [--tags a,b] [--json]");
+    println!("todo list [--filter filtre] [--tags a,b] [--json]");
+    println!("todo done id [--cascade] [--json]");
+    println!("todo export [--format backup|csv|todotxt|markdown|ics] [--filter filtre] [--tags a,b]");
+    println!("  serve            lance le serveur web (commande par défaut)");
+    println!("  add              ajoute une tâche (le projet doit exister)");
+    println!("  list             affiche les tâches d'un filtre (DailyWork par défaut) en tableau ou en JSON");
+    println!("  done             termine une tâche, et ses sous-tâches ouvertes avec --cascade");
+    println!("  export           écrit sur la sortie standard la sauvegarde JSON de la base (par défaut)");
+    println!("                   ou les tâches d'un filtre (All par défaut) au format demandé");
+    println!("  -p, --port port  port d'écoute du serveur (3000 par défaut)");
+    println!("  -d, --db path    fichier de la base sqlite (sinon $TODO_DB, sinon le");
+    println!("                   répertoire de données utilisateur, ex : ~/.local/share/todo/todo.db)");
+    println!("  -r, --retention days  jours avant la purge de la corbeille (sinon $TODO_RETENTION_DAYS,");
+    println!("                   30 par défaut, 0 pour ne jamais purger)");
+    println!("Priorité : VeryUrgent, Urgent, Normal, NotUrgent ; importance : VeryImportant, Important,");
+    println!("Normal, NotImportant ; durée : VeryLong, Long, Normal, Short, VeryShort (casse et « _ » libres).");
+    println!("Filtres : DailyWork, WorkCompleted, All, Blocked, Quick, UnClassified, ByProject, Overdue,");
+    println!("DueToday, DueThisWeek.");
+    println!("Ex :");
+    println!("todo -p 8080 --db ./test.db");
+    println!("todo add \"Appeler le plombier\" --priority urgent --project Maison --due 2026-10-20");
+    println!("todo list --filter DailyWork --json");
+}
+
 // filtre de template pour afficher les dates en jj/mm/aa dans la template en laissant le format AAA-MM-JJ dans la bdd
 
 pub fn format_date(value: Value) -> Result<Value, Error> {
@@ -102,4 +119,61 @@ pub fn format_date(value: Value) -> Result<Value, Error> {
     } else {
         Ok(Value::from(""))
     }
-}
\ No newline at end of file
+}
+
+// filtre de template pour afficher les horodatages en jj/mm/aa hh:mm
+
+pub fn format_datetime(value: Value) -> Result<Value, Error> {
+    let datetime = value
+        .as_str()
+        .and_then(|s| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f").ok());
+    match datetime {
+        Some(datetime) => Ok(Value::from(datetime.format("%d/%m/%y %H:%M").to_string())),
+        None => Ok(Value::from("")),
+    }
+}
+
+// filtre de template pour afficher un temps passé en secondes : 45 s, 12 min, 1 h 05
+
+pub fn format_seconds(seconds: i64) -> String {
+    match seconds {
+        ..60 => format!("{} s", seconds.max(0)),
+        60..3600 => format!("{} min", seconds / 60),
+        _ => format!("{} h {:02}", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
+// filtre de template qui échappe le html d'un résultat de recherche et surligne les termes trouvés
+
+pub fn highlight(value: String) -> Value {
+    let html = value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace(MATCH_START, "<mark>")
+        .replace(MATCH_END, "</mark>");
+    // texte déjà échappé ci-dessus, à ne pas échapper de nouveau
+    Value::from_safe_string(html)
+}
+
+// filtre de template : vrai si la tâche est encore ouverte et que son échéance est dépassée
+
+pub fn overdue(task: Value) -> Result<bool, Error> {
+    let status: Status = serde::Deserialize::deserialize(task.get_attr("status")?)?;
+    let due_date = task.get_attr("due_date")?;
+    let Some(due_date) = due_date.as_str().and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()) else {
+        return Ok(false);
+    };
+    let due_time = task.get_attr("due_time")?.as_str().and_then(|s| NaiveTime::parse_from_str(s, "%H:%M:%S%.f").ok());
+    Ok(!status.is_resolved() && deadline(due_date, due_time) < chrono::Local::now().naive_local())
+}
+
+// filtre de template : libellé d'une règle de récurrence enregistrée (ex : weekly:mon,thu)
+
+pub fn recurrence_label(value: String) -> String {
+    value
+        .parse::<Recurrence>()
+        .map(|recurrence| recurrence.label())
+        .unwrap_or_default()
+}
@@ -0,0 +1,141 @@
+use std::fmt::Write;
+
+// Graphiques SVG générés côté serveur pour la page de statistiques : barres groupées et
+// aires empilées, avec une graduation verticale et des libellés sous l'axe horizontal
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 260.0;
+const LEFT: f64 = 40.0;
+const RIGHT: f64 = 10.0;
+const TOP: f64 = 30.0;
+const BOTTOM: f64 = 30.0;
+// nombre maximum de libellés sous l'axe horizontal
+const MAX_LABELS: usize = 15;
+
+// Une série de valeurs, une par libellé de l'axe horizontal
+
+pub struct Series<'a> {
+    pub label: &'a str,
+    pub color: &'a str,
+    pub values: Vec<f64>,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// valeur maximale de l'axe vertical, arrondie pour avoir des graduations lisibles
+fn axis_max(max: f64) -> f64 {
+    if max <= 0.0 {
+        return 1.0;
+    }
+    let magnitude = 10f64.powf(max.log10().floor());
+    [1.0, 2.0, 2.5, 5.0, 10.0]
+        .into_iter()
+        .map(|step| step * magnitude)
+        .find(|value| *value >= max)
+        .unwrap_or(10.0 * magnitude)
+}
+
+fn y(value: f64, max: f64) -> f64 {
+    TOP + (HEIGHT - TOP - BOTTOM) * (1.0 - value / max)
+}
+
+// début du graphique : graduations horizontales et légende des séries
+fn open(svg: &mut String, max: f64, series: &[Series]) {
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {WIDTH} {HEIGHT}" class="w-100" font-size="11" font-family="sans-serif">"#
+    );
+    for i in 0..=4 {
+        let value = max * f64::from(i) / 4.0;
+        let y = y(value, max);
+        let _ = write!(
+            svg,
+            r##"<line x1="{LEFT}" x2="{}" y1="{y}" y2="{y}" stroke="#dee2e6"/><text x="{}" y="{}" text-anchor="end">{}</text>"##,
+            WIDTH - RIGHT,
+            LEFT - 4.0,
+            y + 4.0,
+            (value * 10.0).round() / 10.0
+        );
+    }
+    let mut x = LEFT;
+    for serie in series {
+        let _ = write!(
+            svg,
+            r#"<rect x="{x}" y="8" width="10" height="10" fill="{}"/><text x="{}" y="17">{}</text>"#,
+            serie.color,
+            x + 14.0,
+            escape(serie.label)
+        );
+        x += 24.0 + 7.0 * serie.label.chars().count() as f64;
+    }
+}
+
+// libellés de l'axe horizontal, un sur n quand ils sont trop nombreux
+fn x_labels(svg: &mut String, labels: &[String], x: impl Fn(usize) -> f64) {
+    let step = labels.len().div_ceil(MAX_LABELS).max(1);
+    for (i, label) in labels.iter().enumerate().step_by(step) {
+        let _ = write!(svg, r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#, x(i), HEIGHT - BOTTOM + 16.0, escape(label));
+    }
+}
+
+// barres groupées : pour chaque libellé, une barre par série
+pub fn bars(labels: &[String], series: &[Series]) -> String {
+    let max = axis_max(series.iter().flat_map(|serie| serie.values.iter().copied()).fold(0.0, f64::max));
+    let mut svg = String::new();
+    open(&mut svg, max, if series.len() > 1 { series } else { &[] });
+    let slot = (WIDTH - LEFT - RIGHT) / labels.len().max(1) as f64;
+    let width = slot * 0.8 / series.len().max(1) as f64;
+    for (i, label) in labels.iter().enumerate() {
+        for (j, serie) in series.iter().enumerate() {
+            let value = serie.values.get(i).copied().unwrap_or_default();
+            let top = y(value, max);
+            let _ = write!(
+                svg,
+                r#"<rect x="{}" y="{top}" width="{width}" height="{}" fill="{}"><title>{} : {value}</title></rect>"#,
+                LEFT + slot * i as f64 + slot * 0.1 + width * j as f64,
+                y(0.0, max) - top,
+                serie.color,
+                escape(label)
+            );
+        }
+    }
+    x_labels(&mut svg, labels, |i| LEFT + slot * (i as f64 + 0.5));
+    svg.push_str("</svg>");
+    svg
+}
+
+// aires empilées : la première série est en bas, chaque série s'ajoute aux précédentes
+pub fn stacked_areas(labels: &[String], series: &[Series]) -> String {
+    let mut totals = vec![0.0; labels.len()];
+    let mut layers = vec![];
+    for serie in series {
+        let bottom = totals.clone();
+        for (total, value) in totals.iter_mut().zip(&serie.values) {
+            *total += value;
+        }
+        layers.push((bottom, totals.clone()));
+    }
+    let max = axis_max(totals.iter().copied().fold(0.0, f64::max));
+    let mut svg = String::new();
+    open(&mut svg, max, series);
+    let x = |i: usize| match labels.len() {
+        0 | 1 => (LEFT + WIDTH - RIGHT) / 2.0,
+        n => LEFT + (WIDTH - LEFT - RIGHT) * i as f64 / (n - 1) as f64,
+    };
+    for (serie, (bottom, top)) in series.iter().zip(&layers) {
+        let mut points: Vec<String> = top.iter().enumerate().map(|(i, value)| format!("{},{}", x(i), y(*value, max))).collect();
+        points.extend(bottom.iter().enumerate().rev().map(|(i, value)| format!("{},{}", x(i), y(*value, max))));
+        let _ = write!(
+            svg,
+            r#"<polygon points="{}" fill="{}" fill-opacity="0.8"><title>{}</title></polygon>"#,
+            points.join(" "),
+            serie.color,
+            escape(serie.label)
+        );
+    }
+    x_labels(&mut svg, labels, x);
+    svg.push_str("</svg>");
+    svg
+}
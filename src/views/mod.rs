@@ -1 +1,2 @@
+pub mod chart;
 pub mod template;
\ No newline at end of file
@@ -1,16 +1,117 @@
-use minijinja::Environment;
+use minijinja::{AutoEscape, Environment};
 
 pub fn add_template(env: &mut Environment<'_>) {
+    // les noms des templates n'ont pas d'extension .html : l'échappement HTML des
+    // valeurs est activé pour tous
+    env.set_auto_escape_callback(|_| AutoEscape::Html);
     env.add_template("main", include_str!("./layout/main.html"))
         .unwrap();
     env.add_template("menu", include_str!("./layout/menu.html"))
         .unwrap();
     env.add_template("task.index", include_str!("./task/index.html"))
         .unwrap();
+    env.add_template("task.row", include_str!("./task/row.html"))
+        .unwrap();
+    env.add_template("task.trash", include_str!("./task/trash.html"))
+        .unwrap();
+    env.add_template("task.cascade", include_str!("./task/cascade.html"))
+        .unwrap();
     env.add_template("task.create", include_str!("./task/create.html"))
         .unwrap();
     env.add_template("task.edit", include_str!("./task/edit.html"))
         .unwrap();
+    env.add_template("task.recurrence", include_str!("./task/recurrence.html"))
+        .unwrap();
+    env.add_template("project.index", include_str!("./project/index.html"))
+        .unwrap();
+    env.add_template("project.show", include_str!("./project/show.html"))
+        .unwrap();
+    env.add_template("project.edit", include_str!("./project/edit.html"))
+        .unwrap();
+    env.add_template("project.badge", include_str!("./project/badge.html"))
+        .unwrap();
+    env.add_template("dashboard.matrix", include_str!("./dashboard/matrix.html"))
+        .unwrap();
+    env.add_template("dashboard.kanban", include_str!("./dashboard/kanban.html"))
+        .unwrap();
+    env.add_template("dashboard.board", include_str!("./dashboard/board.html"))
+        .unwrap();
+    env.add_template("dashboard.card", include_str!("./dashboard/card.html"))
+        .unwrap();
+    env.add_template("backup.index", include_str!("./backup/index.html"))
+        .unwrap();
+    env.add_template("calendar.import", include_str!("./calendar/import.html"))
+        .unwrap();
+    env.add_template("todo_txt.import", include_str!("./todo_txt/import.html"))
+        .unwrap();
+    env.add_template("csv.import", include_str!("./csv/import.html"))
+        .unwrap();
+    env.add_template("timer.timesheet", include_str!("./timer/timesheet.html"))
+        .unwrap();
+    env.add_template("stats.index", include_str!("./stats/index.html"))
+        .unwrap();
+    env.add_template("settings.scoring", include_str!("./settings/scoring.html"))
+        .unwrap();
     env.add_template("home", include_str!("./home.html"))
         .unwrap();
+    env.add_template("error", include_str!("./error.html"))
+        .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::project::Project;
+    use crate::models::task::{MATCH_END, MATCH_START, Task};
+    use crate::utils;
+    use minijinja::context;
+    use std::collections::HashMap;
+
+    // environnement construit comme au lancement du serveur
+    fn environment() -> Environment<'static> {
+        let mut env = Environment::new();
+        add_template(&mut env);
+        env.add_filter("format_date", utils::format_date);
+        env.add_filter("format_datetime", utils::format_datetime);
+        env.add_filter("format_seconds", utils::format_seconds);
+        env.add_filter("highlight", utils::highlight);
+        env.add_filter("overdue", utils::overdue);
+        env.add_filter("recurrence_label", utils::recurrence_label);
+        env
+    }
+
+    #[test]
+    fn user_text_is_escaped() {
+        let task = Task { id: 1, description: "<script>alert(1)</script> & co".to_string(), project_id: Some(2), ..Task::default() };
+        let project = Project { id: 2, name: "<i>Maison</i>".to_string(), ..Project::default() };
+        let rendered = environment()
+            .get_template("task.row")
+            .unwrap()
+            .render(context! {
+                task => task,
+                depths => HashMap::from([(1, 0)]),
+                progress => HashMap::<u32, (u32, u32)>::new(),
+                time_spent => HashMap::<u32, i64>::new(),
+                task_tags => HashMap::from([(1, vec!["<b>tag</b>".to_string()])]),
+                projects => HashMap::from([(2, project)]),
+                waiting => HashMap::from([(1, vec![(3, "\"><img src=x>".to_string())])]),
+            })
+            .unwrap();
+        assert!(rendered.contains("&lt;script&gt;alert(1)&lt;&#x2f;script&gt; &amp; co"), "{rendered}");
+        assert!(rendered.contains("&lt;i&gt;Maison&lt;&#x2f;i&gt;"));
+        assert!(rendered.contains("&lt;b&gt;tag&lt;&#x2f;b&gt;"));
+        assert!(rendered.contains("&quot;&gt;&lt;img src=x&gt;"));
+        for raw in ["<script", "<i>", "<b>", "<img"] {
+            assert!(!rendered.contains(raw), "{raw}");
+        }
+    }
+
+    // le filtre highlight échappe lui-même le texte et ne doit pas être échappé de nouveau
+    #[test]
+    fn highlight_is_escaped_once() {
+        let rendered = environment()
+            .render_str("{{ text | highlight }}", context! { text => format!("<b>{}plomb{}ier</b>", MATCH_START, MATCH_END) })
+            .unwrap();
+        assert_eq!(rendered, "&lt;b&gt;<mark>plomb</mark>ier&lt;/b&gt;");
+    }
 }